@@ -1,3 +1,6 @@
+// the hand-written graphs mirror the macro graphs, including constant nodes
+#![allow(clippy::eq_op)]
+
 use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, Throughput};
 
 mod var {
@@ -10,9 +10,9 @@ mod unpin {
             let mut val_n = *n;
             while val_a != val_n {
                 if val_a > val_n {
-                    val_a = val_a - val_n;
+                    val_a -= val_n;
                 } else {
-                    val_n = val_n - val_a;
+                    val_n -= val_a;
                 }
             }
             val_a
@@ -33,9 +33,9 @@ mod pin {
             let mut val_n = *n;
             while val_a != val_n {
                 if val_a > val_n {
-                    val_a = val_a - val_n;
+                    val_a -= val_n;
                 } else {
-                    val_n = val_n - val_a;
+                    val_n -= val_a;
                 }
             }
             val_a
@@ -1,4 +1,4 @@
-use carboxyl::{Signal, Sink, Stream};
+use carboxyl::{Signal, Sink};
 
 fn main() {
     let sink: Sink<String> = Sink::new();
@@ -15,8 +15,8 @@ fn main() {
     let room2 = Signal::new(vec![String::from("Me: a constant message")]);
     let room_list = Signal::new(vec![room1, room2]);
     let index = Signal::new(0);
-    let index_c = index.clone();
-    let selected_room = room_list.map(move |room_list| {
+    let _index_c = index.clone();
+    let _selected_room = room_list.map(move |room_list| {
         let idx = index.sample();
         room_list[idx].clone()
     });
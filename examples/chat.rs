@@ -24,16 +24,14 @@ fn main() {
 
     prog.init();
 
-    sink.send_name(format!("Alice"));
-    sink.send_text(format!("Hi bob!"));
-    sink.send_text(format!("My name is Alice ;)"));
-    sink.send_name(format!("Bob"));
-    sink.send_text(format!("Hi Alice, nice to meet you!"));
+    sink.send_name(String::from("Alice"));
+    sink.send_text(String::from("Hi bob!"));
+    sink.send_text(String::from("My name is Alice ;)"));
+    sink.send_name(String::from("Bob"));
+    sink.send_text(String::from("Hi Alice, nice to meet you!"));
     sink.send_index(1);
-    //    for _ in 0..6 {
-    loop {
-        prog.run();
-    }
+    drop(sink);
+    while prog.run_blocking() == generated::RunStatus::Processed {}
 }
 
 fn observer_cb(history: &Vec<String>) {
@@ -20,9 +20,7 @@ fn main() {
 
     sink.send_x(2);
     prog.init();
-    for _ in 0..5 {
-        prog.run();
-    }
+    prog.run_all();
 }
 
 fn observer_cb(t: &u32) {
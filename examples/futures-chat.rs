@@ -1,11 +1,9 @@
-use executor::ThreadPool;
 use future::ready;
-use futures::{executor, future, pin_mut, stream, stream::Peekable, FutureExt, StreamExt};
+use futures::{executor, future};
 use futures_signals::{
     map_ref,
     signal::{Mutable, SignalExt},
 };
-use std::pin::Pin;
 
 fn main() {
     let x_mut = Mutable::new(1);
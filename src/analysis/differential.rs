@@ -0,0 +1,473 @@
+// differential tests of the generated code against the interpreter
+// random programs are compiled and run with trybuild, all cases of a run are
+// compiled together and only a failing case is shrunk, one build per step
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{env, fs, panic, process, slice};
+
+use petgraph::Graph;
+use proptest::sample::Index;
+use proptest::strategy::{Strategy, ValueTree};
+use proptest::test_runner::{Config, RngAlgorithm, TestRng, TestRunner};
+use proptest::{collection, option};
+
+use super::interpreter::{Interpreter, Signal, Value};
+use super::{validate::validate, visitor::ReVisitor, Family, NodeData, ReEdge, ReNode};
+use crate::parser::ReBlock;
+
+const SOURCES: [(&str, Family); 4] = [
+    ("v0", Family::Variable),
+    ("v1", Family::Variable),
+    ("e0", Family::Event),
+    ("e1", Family::Event),
+];
+
+/// Seed of `cargo test`, so that its programs are the same on every run.
+const DEFAULT_SEED: u64 = 0x5eed;
+
+/// One declaration of a random program: operation, two inputs, a constant
+/// and whether it is pinned.
+type Step = (u8, Index, Index, i64, bool);
+
+/// Values for the sources of one update, in the order of `SOURCES`.
+type Tick = Vec<Option<i64>>;
+
+fn steps() -> impl Strategy<Value = Vec<Step>> {
+    collection::vec(
+        (
+            0u8..12,
+            proptest::arbitrary::any::<Index>(),
+            proptest::arbitrary::any::<Index>(),
+            1i64..10,
+            proptest::bool::ANY,
+        ),
+        4..16,
+    )
+}
+
+fn ticks() -> impl Strategy<Value = Vec<Tick>> {
+    collection::vec(
+        collection::vec(option::weighted(0.6, 0i64..8), SOURCES.len()),
+        1..8,
+    )
+}
+
+/// Declarations of a natgraph like program. Values stay below 1000, so
+/// they fit into `i32` without overflow.
+fn program(steps: &[Step]) -> String {
+    let mut declared: Vec<(String, Family)> = SOURCES
+        .iter()
+        .map(|(name, family)| (name.to_string(), *family))
+        .collect();
+    let mut program = String::from(
+        "let v0 = Var::<i32>(0i32);\n\
+         let v1 = Var::<i32>(5i32);\n\
+         let e0 = Evt::<i32>();\n\
+         let e1 = Evt::<i32>();\n",
+    );
+    for (i, (op, first, second, k, pin)) in steps.iter().enumerate() {
+        let of = |family: Family| -> Vec<(String, Family)> {
+            declared
+                .iter()
+                .filter(|(_, fam)| *fam == family)
+                .cloned()
+                .collect()
+        };
+        let pick = |candidates: &[(String, Family)], index: &Index| {
+            candidates[index.index(candidates.len())].clone()
+        };
+        let other = |candidates: &[(String, Family)], taken: &str, index: &Index| {
+            let rest: Vec<_> = candidates
+                .iter()
+                .filter(|(name, _)| name != taken)
+                .cloned()
+                .collect();
+            pick(&rest, index)
+        };
+        let (vars, evts) = (of(Family::Variable), of(Family::Event));
+        let (a, fa) = pick(&declared, first);
+        let mut feedback = None;
+        let (expr, family) = match op {
+            1 => (format!("{}.map(|_v: &i32| -> i32 {{ {} }})", a, k), fa),
+            2 => {
+                let (b, fb) = other(&declared, &a, second);
+                let family = if fa == Family::Event || fb == Family::Event {
+                    Family::Event
+                } else {
+                    Family::Variable
+                };
+                let expr = format!(
+                    "({}, {}).map(|a: &i32, b: &i32| -> i32 {{ (a + b) % 1000 }})",
+                    a, b
+                );
+                (expr, family)
+            }
+            3 => {
+                let (e, _) = pick(&evts, first);
+                let expr = format!(
+                    "{}.fold({}i32, |s: i32, e: &i32| -> i32 {{ (s + e) % 1000 }})",
+                    e, k
+                );
+                (expr, Family::Variable)
+            }
+            4 => {
+                let ((e, _), (v, _)) = (pick(&evts, first), pick(&vars, second));
+                let expr = format!(
+                    "({}, {}).fold(0i32, |s: i32, e: &i32, v: &i32| -> i32 {{ (s + e + v) % 1000 }})",
+                    e, v
+                );
+                (expr, Family::Variable)
+            }
+            5 => {
+                let (e, _) = pick(&evts, first);
+                let expr = format!(
+                    "{}.filter(|v: &i32| -> bool {{ v % {} != 0 }})",
+                    e,
+                    k % 3 + 2
+                );
+                (expr, Family::Event)
+            }
+            6 => (format!("{}.changed()", pick(&vars, first).0), Family::Event),
+            7 => {
+                let (e, _) = pick(&evts, first);
+                (
+                    format!("{} || {}", e, other(&evts, &e, second).0),
+                    Family::Event,
+                )
+            }
+            8 => {
+                let ((e, _), (v, _)) = (pick(&evts, first), pick(&vars, second));
+                (format!("{}.snapshot({})", e, v), Family::Event)
+            }
+            9 => (
+                format!("{}.hold({}i32)", pick(&evts, first).0, k),
+                Family::Variable,
+            ),
+            10 => (
+                format!("{}.delay({}i32)", pick(&vars, first).0, k),
+                Family::Variable,
+            ),
+            11 => {
+                // feedback cycle, the input of the delay is declared next
+                let (v, _) = pick(&vars, first);
+                feedback = Some(v);
+                (
+                    format!("n{}_next.delay::<i32>({}i32)", i, k),
+                    Family::Variable,
+                )
+            }
+            _ => (
+                format!("{}.map(|v: &i32| -> i32 {{ (v + {}) % 1000 }})", a, k),
+                fa,
+            ),
+        };
+        let name = format!("n{}", i);
+        let pin = if *pin { "pin " } else { "" };
+        writeln!(program, "let {}{} = {};", pin, name, expr).unwrap();
+        declared.push((name.clone(), family));
+        if let Some(v) = feedback {
+            writeln!(
+                program,
+                "let {0}_next = ({0}, {1}).map(|a: &i32, b: &i32| -> i32 {{ (a + b) % 1000 }});",
+                name, v
+            )
+            .unwrap();
+            declared.push((format!("{}_next", name), Family::Variable));
+        }
+    }
+    program
+}
+
+fn analyse(block: &ReBlock) -> Graph<ReNode<'_>, ReEdge> {
+    let mut visitor = ReVisitor::new();
+    visitor.visit_reblock(block).expect("invalid program");
+    validate(&visitor.graph, &visitor.origins).expect("invalid program");
+    visitor.reactive_graph()
+}
+
+/// Names of the reactives with getters and their families, in declaration
+/// order.
+fn pinned(graph: &Graph<ReNode, ReEdge>) -> Vec<(String, Family)> {
+    let mut names: Vec<_> = graph
+        .node_indices()
+        .filter_map(|idx| match &graph[idx] {
+            ReNode::Name(name) if name.pin() => {
+                Some((name.id(), name.id.ident.to_string(), name.family()))
+            }
+            _ => None,
+        })
+        .collect();
+    names.sort_by_key(|(id, _, _)| *id);
+    names
+        .into_iter()
+        .map(|(_, name, family)| (name, family))
+        .collect()
+}
+
+fn literal(value: &Value) -> String {
+    match value {
+        Value::Int(value) => value.to_string(),
+        Value::Bool(value) => value.to_string(),
+    }
+}
+
+/// A program that feeds the ticks into the generated code and asserts the
+/// values and notifications computed by the interpreter.
+fn test_case(program: &str, ticks: &[Tick]) -> String {
+    let block: ReBlock = syn::parse_str(program).expect("invalid program");
+    let graph = analyse(&block);
+    let pinned = pinned(&graph);
+    let mut interpreter = Interpreter::new(&graph);
+
+    let mut main = String::new();
+    for (name, _) in &pinned {
+        writeln!(
+            main,
+            "    let _{0} = {{ let log = log.clone(); prog.subscribe_{0}(move |value: &i32| log.borrow_mut().push((\"{0}\", *value))) }};",
+            name
+        )
+        .unwrap();
+    }
+    for (tick, values) in ticks.iter().enumerate() {
+        let mut inputs = HashMap::new();
+        let mut transaction = String::from("sink.transaction()");
+        for ((source, _), value) in SOURCES.iter().zip(values) {
+            if let Some(value) = value {
+                write!(transaction, ".set_{}({})", source, value).unwrap();
+                inputs.insert(source.to_string(), Value::Int(*value));
+            }
+        }
+        interpreter.tick(&inputs);
+        writeln!(main, "    // tick {}", tick + 1).unwrap();
+        writeln!(main, "    {}.commit().unwrap();", transaction).unwrap();
+        writeln!(main, "    prog.run();").unwrap();
+        let mut notified = Vec::new();
+        for (name, family) in &pinned {
+            let message = format!("\"tick {}: {}\"", tick + 1, name);
+            match (family, interpreter.signal(name)) {
+                (Family::Variable, Signal::Variable { value, change }) => {
+                    let value = literal(value);
+                    writeln!(
+                        main,
+                        "    assert_eq!(*prog.get_{}(), {}, {});",
+                        name, value, message
+                    )
+                    .unwrap();
+                    if *change {
+                        notified.push(format!("(\"{}\", {})", name, value));
+                    }
+                }
+                (Family::Event, Signal::Event(fired)) => {
+                    let expected = match fired {
+                        Some(value) => {
+                            notified.push(format!("(\"{}\", {})", name, literal(value)));
+                            format!("Some(&{})", literal(value))
+                        }
+                        None => String::from("None"),
+                    };
+                    writeln!(
+                        main,
+                        "    assert_eq!(prog.get_{}(), {}, {});",
+                        name, expected, message
+                    )
+                    .unwrap();
+                }
+                _ => panic!("family of `{}` does not match its signal", name),
+            }
+        }
+        // names are unique, so this matches the order of the sorted tuples
+        notified.sort();
+        writeln!(
+            main,
+            "    let mut notified = log.borrow_mut().split_off(0);"
+        )
+        .unwrap();
+        writeln!(main, "    notified.sort();").unwrap();
+        writeln!(
+            main,
+            "    assert_eq!(notified, Vec::<(&str, i32)>::from([{}]), \"tick {}: notified\");",
+            notified.join(", "),
+            tick + 1
+        )
+        .unwrap();
+    }
+
+    format!(
+        "#![allow(deprecated)]\n\n\
+         mod generated {{\n    use rerust::rerust;\n\n    rerust! {{\n{}    }}\n}}\n\n\
+         use std::{{cell::RefCell, rc::Rc}};\n\n\
+         fn main() {{\n    let mut prog = generated::Program::new();\n    let mut sink = prog.sink();\n    \
+         let log = Rc::new(RefCell::new(Vec::new()));\n{}}}\n",
+        program
+            .lines()
+            .map(|line| format!("        {}\n", line))
+            .collect::<String>(),
+        main
+    )
+}
+
+/// Seed of the random programs. Fixed unless `RERUST_DIFFERENTIAL_SEED` is
+/// a number or `random`, which takes it from the clock.
+fn seed() -> u64 {
+    match env::var("RERUST_DIFFERENTIAL_SEED").as_deref() {
+        Ok("random") => SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos() as u64,
+        Ok(seed) => seed
+            .parse()
+            .expect("RERUST_DIFFERENTIAL_SEED is neither a number nor `random`"),
+        Err(_) => DEFAULT_SEED,
+    }
+}
+
+fn config(name: &str, default: usize) -> usize {
+    env::var(name).map_or(default, |value| {
+        value
+            .parse()
+            .unwrap_or_else(|_| panic!("{} is not a number", name))
+    })
+}
+
+/// Directory for the cases of this run. `CARGO_TARGET_TMPDIR` is only set
+/// for integration tests, unit tests are in `target/<profile>/deps`.
+fn case_dir(seed: u64) -> PathBuf {
+    let tmp = match option_env!("CARGO_TARGET_TMPDIR") {
+        Some(tmp) => PathBuf::from(tmp),
+        None => {
+            let exe = env::current_exe().unwrap();
+            exe.ancestors().nth(3).unwrap().join("tmp")
+        }
+    };
+    let dir = tmp.join(format!("rerust-differential-{}-{}", seed, process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Compiles and runs the cases with trybuild, which panics once they are
+/// checked if any of them failed.
+fn passes(cases: &[PathBuf]) -> bool {
+    panic::catch_unwind(|| {
+        let t = trybuild::TestCases::new();
+        for case in cases {
+            t.pass(case);
+        }
+    })
+    .is_ok()
+}
+
+/// Shrinks a failing case like proptest does, every candidate is compiled
+/// on its own. Returns the smallest failing case found within `budget` runs.
+fn shrink(
+    tree: &mut impl ValueTree<Value = (Vec<Step>, Vec<Tick>)>,
+    dir: &Path,
+    budget: usize,
+) -> PathBuf {
+    let mut minimal = dir.join("minimal.rs");
+    let (steps, ticks) = tree.current();
+    fs::write(&minimal, test_case(&program(&steps), &ticks)).unwrap();
+    let mut more = tree.simplify();
+    for run in 0..budget {
+        if !more {
+            break;
+        }
+        let (steps, ticks) = tree.current();
+        let candidate = dir.join(format!("shrink_{}.rs", run));
+        fs::write(&candidate, test_case(&program(&steps), &ticks)).unwrap();
+        if passes(slice::from_ref(&candidate)) {
+            more = tree.complicate();
+        } else {
+            minimal = candidate;
+            more = tree.simplify();
+        }
+    }
+    minimal
+}
+
+/// Runs `RERUST_DIFFERENTIAL_CASES` random programs (12 by default). A failing
+/// case is shrunk with up to `RERUST_DIFFERENTIAL_SHRINK` runs (32 by
+/// default), set `RERUST_DIFFERENTIAL_SEED` to reproduce a run.
+#[test]
+fn interpreter_matches_generated_code() {
+    let seed = seed();
+    let cases = config("RERUST_DIFFERENTIAL_CASES", 12);
+    let budget = config("RERUST_DIFFERENTIAL_SHRINK", 32);
+    let dir = case_dir(seed);
+    println!("RERUST_DIFFERENTIAL_SEED={} in {}", seed, dir.display());
+
+    let mut bytes = [0u8; 32];
+    for chunk in bytes.chunks_mut(8) {
+        chunk.copy_from_slice(&seed.to_le_bytes());
+    }
+    let rng = TestRng::from_seed(RngAlgorithm::ChaCha, &bytes);
+    let mut runner = TestRunner::new_with_rng(Config::default(), rng);
+    let strategy = (steps(), ticks());
+    let mut trees = Vec::new();
+    let mut paths = Vec::new();
+    for case in 0..cases {
+        let tree = strategy.new_tree(&mut runner).unwrap();
+        let (steps, ticks) = tree.current();
+        let path = dir.join(format!("case_{}.rs", case));
+        fs::write(&path, test_case(&program(&steps), &ticks)).unwrap();
+        trees.push(tree);
+        paths.push(path);
+    }
+    if passes(&paths) {
+        fs::remove_dir_all(&dir).unwrap();
+        return;
+    }
+
+    // find the first failing case on its own, then shrink it
+    let failing = paths
+        .iter()
+        .position(|path| !passes(slice::from_ref(path)))
+        .expect("cases only fail together");
+    let minimal = shrink(&mut trees[failing], &dir, budget);
+    panic!(
+        "generated code differs from the interpreter, rerun with RERUST_DIFFERENTIAL_SEED={}, \
+         smallest failing case: {}",
+        seed,
+        minimal.display()
+    );
+}
+
+#[test]
+fn interpreter_semantics() {
+    let program = "
+        let x = Var::<i32>(1i32);
+        let e = Evt::<i32>();
+        let y = x.map(|x: &i32| -> i32 { x * 2 });
+        let pin z = (x, y).map(|x: &i32, y: &i32| -> i32 { x + y });
+        let pin last = x.delay(0i32);
+        let pin seen = (e || x.changed()).hold(0i32);
+        let pin sum = e.snapshot(z).fold(0i32, |s: i32, z: &i32| -> i32 { s + z });
+    ";
+    let block: ReBlock = syn::parse_str(program).unwrap();
+    let graph = analyse(&block);
+    let mut interpreter = Interpreter::new(&graph);
+    let int = |name: &str, interpreter: &Interpreter| match interpreter.signal(name) {
+        Signal::Variable { value, .. } => value.clone(),
+        Signal::Event(_) => panic!("`{}` is an event", name),
+    };
+    assert_eq!(int("z", &interpreter), Value::Int(3));
+    assert_eq!(int("last", &interpreter), Value::Int(0));
+
+    let tick: HashMap<_, _> = vec![(String::from("x"), Value::Int(2))]
+        .into_iter()
+        .collect();
+    interpreter.tick(&tick);
+    assert_eq!(int("z", &interpreter), Value::Int(6));
+    assert_eq!(int("last", &interpreter), Value::Int(1));
+    assert_eq!(int("seen", &interpreter), Value::Int(2));
+
+    let tick: HashMap<_, _> = vec![(String::from("e"), Value::Int(7))]
+        .into_iter()
+        .collect();
+    interpreter.tick(&tick);
+    assert_eq!(int("last", &interpreter), Value::Int(2));
+    assert_eq!(int("seen", &interpreter), Value::Int(7));
+    assert_eq!(int("sum", &interpreter), Value::Int(6));
+}
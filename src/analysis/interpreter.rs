@@ -0,0 +1,307 @@
+// reference semantics for the generated code
+// every node keeps its value, so the result does not depend on pinning
+// closures are evaluated for the subset of Rust used by the differential tests
+
+use std::collections::HashMap;
+
+use petgraph::{
+    graph::NodeIndex,
+    visit::{EdgeFiltered, EdgeRef, Topo},
+    Graph,
+};
+use syn::{BinOp, Expr, Lit, Pat, Stmt, UnOp};
+
+use super::{is_propagating, Family, NodeData, ReEdge, ReNode, Role};
+use crate::parser::ReFunction;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Int(i64),
+    Bool(bool),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Signal {
+    Variable { value: Value, change: bool },
+    Event(Option<Value>),
+}
+
+pub struct Interpreter<'g, 'ast> {
+    graph: &'g Graph<ReNode<'ast>, ReEdge>,
+    order: Vec<NodeIndex>,
+    signals: HashMap<NodeIndex, Signal>,
+}
+
+impl<'g, 'ast> Interpreter<'g, 'ast> {
+    /// Evaluates the initial values like `Program::default_state()`.
+    pub fn new(graph: &'g Graph<ReNode<'ast>, ReEdge>) -> Self {
+        let propagation = EdgeFiltered::from_fn(graph, |edge| is_propagating(graph, edge));
+        let mut topo = Topo::new(&propagation);
+        let mut order = Vec::new();
+        while let Some(idx) = topo.next(&propagation) {
+            order.push(idx);
+        }
+        let mut interpreter = Self {
+            graph,
+            order,
+            signals: HashMap::new(),
+        };
+        interpreter.update(None, &HashMap::new());
+        interpreter
+    }
+
+    /// One update with the values of the sources set by the input, keyed by
+    /// the names of the sources.
+    pub fn tick(&mut self, inputs: &HashMap<String, Value>) {
+        let previous = self.signals.clone();
+        self.update(Some(&previous), inputs);
+    }
+
+    /// Signal of the reactive declared with `name`.
+    pub fn signal(&self, name: &str) -> &Signal {
+        let idx = self
+            .graph
+            .node_indices()
+            .find(|idx| matches!(&self.graph[*idx], ReNode::Name(node) if node.id.ident == name))
+            .expect("unknown reactive");
+        &self.signals[&idx]
+    }
+
+    fn update(
+        &mut self,
+        previous: Option<&HashMap<NodeIndex, Signal>>,
+        inputs: &HashMap<String, Value>,
+    ) {
+        for idx in self.order.clone() {
+            let signal = self.evaluate(idx, previous, inputs);
+            self.signals.insert(idx, signal);
+        }
+    }
+
+    /// Without previous signals the node is initialized.
+    fn evaluate(
+        &self,
+        idx: NodeIndex,
+        previous: Option<&HashMap<NodeIndex, Signal>>,
+        inputs: &HashMap<String, Value>,
+    ) -> Signal {
+        let incoming = self.incoming(idx);
+        let old = previous.and_then(|previous| match &previous[&idx] {
+            Signal::Variable { value, .. } => Some(value.clone()),
+            Signal::Event(_) => None,
+        });
+        match &self.graph[idx] {
+            ReNode::Var(node) => {
+                let input = inputs.get(&self.source_name(idx)).cloned();
+                let current = old
+                    .clone()
+                    .unwrap_or_else(|| eval(node.initial, &Env::new()));
+                variable(input.unwrap_or(current), old)
+            }
+            ReNode::Evt(_) => Signal::Event(inputs.get(&self.source_name(idx)).cloned()),
+            ReNode::Name(_) => self.signals[&incoming[0]].clone(),
+            ReNode::Map(node) => {
+                let args = self.arguments(&incoming);
+                match node.family() {
+                    Family::Event => Signal::Event(args.map(|args| call(node.update_expr, args))),
+                    Family::Variable => {
+                        let args = args.expect("variables always have a value");
+                        variable(call(node.update_expr, args), old)
+                    }
+                }
+            }
+            ReNode::Fold(node) => {
+                let current = old
+                    .clone()
+                    .unwrap_or_else(|| eval(node.initial, &Env::new()));
+                let next = match self.arguments(&incoming) {
+                    Some(args) => {
+                        let mut params = vec![current];
+                        params.extend(args);
+                        call(node.update_expr, params)
+                    }
+                    None => current,
+                };
+                variable(next, old)
+            }
+            ReNode::Filter(node) => {
+                let fired = self.arguments(&incoming).and_then(|args| {
+                    let passed = call(node.filter_expr, args.clone()) == Value::Bool(true);
+                    args.into_iter().next().filter(|_| passed)
+                });
+                Signal::Event(fired)
+            }
+            ReNode::Changed(_) => match &self.signals[&incoming[0]] {
+                Signal::Variable {
+                    value,
+                    change: true,
+                } => Signal::Event(Some(value.clone())),
+                _ => Signal::Event(None),
+            },
+            ReNode::Merge(_) => {
+                let left = fired(&self.signals[&self.operand(idx, Role::Left)]);
+                let right = fired(&self.signals[&self.operand(idx, Role::Right)]);
+                Signal::Event(left.or(right))
+            }
+            ReNode::Snapshot(_) => {
+                let trigger = fired(&self.signals[&self.operand(idx, Role::Trigger)]);
+                let sampled = value(&self.signals[&self.operand(idx, Role::Sampled)]);
+                Signal::Event(trigger.map(|_| sampled))
+            }
+            ReNode::Hold(node) => {
+                let current = old
+                    .clone()
+                    .unwrap_or_else(|| eval(node.initial, &Env::new()));
+                variable(fired(&self.signals[&incoming[0]]).unwrap_or(current), old)
+            }
+            // the value of the input after the previous update
+            ReNode::Delay(node) => match previous {
+                Some(previous) => variable(value(&previous[&incoming[0]]), old),
+                None => variable(eval(node.initial, &Env::new()), None),
+            },
+        }
+    }
+
+    /// Incoming nodes in declaration order.
+    fn incoming(&self, idx: NodeIndex) -> Vec<NodeIndex> {
+        let mut edges: Vec<_> = self.graph.edges_directed(idx, petgraph::Incoming).collect();
+        edges.sort_by_key(|edge| edge.id());
+        edges.iter().map(|edge| edge.source()).collect()
+    }
+
+    fn operand(&self, idx: NodeIndex, role: Role) -> NodeIndex {
+        self.graph
+            .edges_directed(idx, petgraph::Incoming)
+            .find(|edge| edge.weight().role == role)
+            .map(|edge| edge.source())
+            .expect("missing operand")
+    }
+
+    fn source_name(&self, idx: NodeIndex) -> String {
+        self.graph
+            .neighbors_directed(idx, petgraph::Outgoing)
+            .find_map(|next| match &self.graph[next] {
+                ReNode::Name(name) => Some(name.id.ident.to_string()),
+                _ => None,
+            })
+            .expect("source without name")
+    }
+
+    /// Values of the incoming reactives, if all incoming events fired.
+    fn arguments(&self, incoming: &[NodeIndex]) -> Option<Vec<Value>> {
+        incoming
+            .iter()
+            .map(|idx| match &self.signals[idx] {
+                Signal::Variable { value, .. } => Some(value.clone()),
+                Signal::Event(value) => value.clone(),
+            })
+            .collect()
+    }
+}
+
+fn variable(value: Value, old: Option<Value>) -> Signal {
+    let change = matches!(old, Some(old) if old != value);
+    Signal::Variable { value, change }
+}
+
+fn value(signal: &Signal) -> Value {
+    match signal {
+        Signal::Variable { value, .. } => value.clone(),
+        Signal::Event(_) => panic!("event has no value"),
+    }
+}
+
+fn fired(signal: &Signal) -> Option<Value> {
+    match signal {
+        Signal::Event(value) => value.clone(),
+        Signal::Variable { .. } => panic!("variable does not fire"),
+    }
+}
+
+type Env = HashMap<String, Value>;
+
+fn call(function: &ReFunction, args: Vec<Value>) -> Value {
+    let closure = match function {
+        ReFunction::Closure(closure) => closure,
+        ReFunction::Path(_) => panic!("function paths are not supported"),
+    };
+    let mut env = Env::new();
+    for (pat, arg) in closure.inputs.iter().zip(args) {
+        bind(pat, arg, &mut env);
+    }
+    eval(&closure.body, &env)
+}
+
+fn bind(pat: &Pat, arg: Value, env: &mut Env) {
+    match pat {
+        Pat::Type(pat) => bind(&pat.pat, arg, env),
+        Pat::Reference(pat) => bind(&pat.pat, arg, env),
+        Pat::Ident(pat) => {
+            env.insert(pat.ident.to_string(), arg);
+        }
+        Pat::Wild(_) => {}
+        _ => panic!("unsupported closure parameter"),
+    }
+}
+
+/// References are transparent, all integer types are `i64`.
+fn eval(expr: &Expr, env: &Env) -> Value {
+    match expr {
+        Expr::Lit(expr) => match &expr.lit {
+            Lit::Int(lit) => Value::Int(lit.base10_parse().expect("integer literal")),
+            Lit::Bool(lit) => Value::Bool(lit.value),
+            _ => panic!("unsupported literal"),
+        },
+        Expr::Path(path) => {
+            let ident = path.path.get_ident().expect("unsupported path");
+            env.get(&ident.to_string())
+                .cloned()
+                .expect("unknown variable")
+        }
+        Expr::Paren(expr) => eval(&expr.expr, env),
+        Expr::Reference(expr) => eval(&expr.expr, env),
+        Expr::Unary(expr) => match (expr.op, eval(&expr.expr, env)) {
+            (UnOp::Deref(_), value) => value,
+            (UnOp::Neg(_), Value::Int(value)) => Value::Int(-value),
+            (UnOp::Not(_), Value::Bool(value)) => Value::Bool(!value),
+            _ => panic!("unsupported unary expression"),
+        },
+        Expr::Binary(expr) => binary(expr.op, eval(&expr.left, env), eval(&expr.right, env)),
+        Expr::Block(expr) => match expr.block.stmts.as_slice() {
+            [Stmt::Expr(expr)] => eval(expr, env),
+            _ => panic!("unsupported block"),
+        },
+        Expr::If(expr) => {
+            let branch = match eval(&expr.cond, env) {
+                Value::Bool(true) => match expr.then_branch.stmts.as_slice() {
+                    [Stmt::Expr(expr)] => expr,
+                    _ => panic!("unsupported block"),
+                },
+                Value::Bool(false) => &expr.else_branch.as_ref().expect("if without else").1,
+                Value::Int(_) => panic!("condition is not a bool"),
+            };
+            eval(branch, env)
+        }
+        _ => panic!("unsupported expression"),
+    }
+}
+
+fn binary(op: BinOp, left: Value, right: Value) -> Value {
+    use Value::{Bool, Int};
+    match (op, left, right) {
+        (BinOp::Add(_), Int(l), Int(r)) => Int(l + r),
+        (BinOp::Sub(_), Int(l), Int(r)) => Int(l - r),
+        (BinOp::Mul(_), Int(l), Int(r)) => Int(l * r),
+        (BinOp::Div(_), Int(l), Int(r)) => Int(l / r),
+        (BinOp::Rem(_), Int(l), Int(r)) => Int(l % r),
+        (BinOp::Lt(_), Int(l), Int(r)) => Bool(l < r),
+        (BinOp::Le(_), Int(l), Int(r)) => Bool(l <= r),
+        (BinOp::Gt(_), Int(l), Int(r)) => Bool(l > r),
+        (BinOp::Ge(_), Int(l), Int(r)) => Bool(l >= r),
+        (BinOp::Eq(_), l, r) => Bool(l == r),
+        (BinOp::Ne(_), l, r) => Bool(l != r),
+        (BinOp::And(_), Bool(l), Bool(r)) => Bool(l && r),
+        (BinOp::Or(_), Bool(l), Bool(r)) => Bool(l || r),
+        _ => panic!("unsupported binary expression"),
+    }
+}
@@ -1,9 +1,15 @@
-use syn::{Expr, Type};
+use petgraph::{graph::EdgeReference, visit::EdgeRef, Graph};
+use syn::{Expr, Ident, Type, TypeInfer};
 
 use enum_dispatch::enum_dispatch;
 
-use crate::parser::{ReClosure, ReIdent};
+use crate::parser::{ReFunction, ReIdent};
 
+#[cfg(test)]
+mod differential;
+#[cfg(test)]
+mod interpreter;
+pub mod validate;
 pub mod visitor;
 
 #[derive(Debug, Clone)]
@@ -34,6 +40,10 @@ pub enum ReNode<'ast> {
     Map(MapNode<'ast>),
     Filter(FilterNode<'ast>),
     Changed(ChangedNode),
+    Merge(MergeNode),
+    Snapshot(SnapshotNode),
+    Hold(HoldNode<'ast>),
+    Delay(DelayNode<'ast>),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -47,6 +57,16 @@ pub struct ChangedNode {
     pub data: ReData,
 }
 
+#[derive(Debug)]
+pub struct MergeNode {
+    pub data: ReData,
+}
+
+#[derive(Debug)]
+pub struct SnapshotNode {
+    pub data: ReData,
+}
+
 #[derive(Debug)]
 pub struct EvtNode {
     pub data: ReData,
@@ -67,25 +87,126 @@ pub struct NameNode<'ast> {
 #[derive(Debug)]
 pub struct FoldNode<'ast> {
     pub initial: &'ast Expr,
-    pub update_expr: &'ast ReClosure,
+    pub update_expr: &'ast ReFunction,
+    pub data: ReData,
+}
+
+#[derive(Debug)]
+pub struct HoldNode<'ast> {
+    pub initial: &'ast Expr,
+    pub data: ReData,
+}
+
+#[derive(Debug)]
+pub struct DelayNode<'ast> {
+    pub initial: &'ast Expr,
+    /// input declared after the delay, resolved once the block is visited
+    pub forward: Option<&'ast Ident>,
     pub data: ReData,
 }
 
 #[derive(Debug)]
 pub struct MapNode<'ast> {
-    pub update_expr: &'ast ReClosure,
+    pub update_expr: &'ast ReFunction,
     pub data: ReData,
 }
 
 #[derive(Debug)]
 pub struct FilterNode<'ast> {
-    pub filter_expr: &'ast ReClosure,
+    pub filter_expr: &'ast ReFunction,
     pub data: ReData,
 }
 
+/// Type of reactives without annotation, left for rustc to infer.
+pub fn inferred_type() -> Type {
+    Type::Infer(TypeInfer {
+        underscore_token: Default::default(),
+    })
+}
+
+pub fn is_inferred(ty: &Type) -> bool {
+    match ty {
+        Type::Infer(_) => true,
+        Type::Reference(reference) => is_inferred(&reference.elem),
+        _ => false,
+    }
+}
+
+/// Whether two types are different for sure. Paths other than primitive
+/// types may be aliases or written differently, so they are left to rustc.
+pub fn mismatched(found: &Type, expected: &Type) -> bool {
+    match (found, expected) {
+        (Type::Paren(found), _) => mismatched(&found.elem, expected),
+        (_, Type::Paren(expected)) => mismatched(found, &expected.elem),
+        (Type::Reference(found), Type::Reference(expected)) => {
+            found.mutability.is_some() != expected.mutability.is_some()
+                || mismatched(&found.elem, &expected.elem)
+        }
+        // a value of the referenced type, like `String` for `&String`
+        (Type::Reference(reference), other) | (other, Type::Reference(reference)) => {
+            primitive(other).is_some() || (!is_inferred(other) && *reference.elem == *other)
+        }
+        (Type::Tuple(found), Type::Tuple(expected)) => {
+            found.elems.len() != expected.elems.len()
+                || found
+                    .elems
+                    .iter()
+                    .zip(&expected.elems)
+                    .any(|(found, expected)| mismatched(found, expected))
+        }
+        _ => matches!(
+            (primitive(found), primitive(expected)),
+            (Some(found), Some(expected)) if found != expected
+        ),
+    }
+}
+
+fn primitive(ty: &Type) -> Option<&Ident> {
+    const PRIMITIVES: [&str; 17] = [
+        "bool", "char", "str", "f32", "f64", "i8", "i16", "i32", "i64", "i128", "isize", "u8",
+        "u16", "u32", "u64", "u128", "usize",
+    ];
+    match ty {
+        Type::Path(path) if path.qself.is_none() => path
+            .path
+            .get_ident()
+            .filter(|ident| PRIMITIVES.iter().any(|primitive| *ident == primitive)),
+        _ => None,
+    }
+}
+
+/// Edges into a delay node only carry values to the next update, so they are
+/// not part of the propagation order and may close a cycle.
+pub fn is_propagating(graph: &Graph<ReNode, ReEdge>, edge: EdgeReference<ReEdge>) -> bool {
+    !matches!(graph[edge.target()], ReNode::Delay(_))
+}
+
 #[derive(Clone, Debug)]
 pub struct ReEdge {
+    #[allow(dead_code)]
     ty: Type,
+    pub role: Role,
+}
+
+impl ReEdge {
+    pub fn new(ty: Type) -> Self {
+        Self::with_role(ty, Role::Input)
+    }
+
+    pub fn with_role(ty: Type, role: Role) -> Self {
+        Self { ty, role }
+    }
+}
+
+/// Which operand an edge is, for reactives whose inputs are not
+/// interchangeable.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    Input,
+    Left,
+    Right,
+    Trigger,
+    Sampled,
 }
 
 impl NodeData for VarNode<'_> {
@@ -268,6 +389,110 @@ impl NodeData for ChangedNode {
     }
 }
 
+impl NodeData for MergeNode {
+    fn outgoing_family(&self) -> Family {
+        self.family()
+    }
+
+    fn family(&self) -> Family {
+        self.data.family()
+    }
+
+    fn ty(&self) -> &Type {
+        self.data.ty()
+    }
+
+    fn pin(&self) -> bool {
+        self.data.pin()
+    }
+
+    fn pin_mut(&mut self) -> &mut bool {
+        self.data.pin_mut()
+    }
+
+    fn id(&self) -> u32 {
+        self.data.id()
+    }
+}
+
+impl NodeData for SnapshotNode {
+    fn outgoing_family(&self) -> Family {
+        Family::Event
+    }
+
+    fn family(&self) -> Family {
+        self.data.family()
+    }
+
+    fn ty(&self) -> &Type {
+        self.data.ty()
+    }
+
+    fn pin(&self) -> bool {
+        self.data.pin()
+    }
+
+    fn pin_mut(&mut self) -> &mut bool {
+        self.data.pin_mut()
+    }
+
+    fn id(&self) -> u32 {
+        self.data.id()
+    }
+}
+
+impl NodeData for HoldNode<'_> {
+    fn outgoing_family(&self) -> Family {
+        Family::Variable
+    }
+
+    fn family(&self) -> Family {
+        self.data.family()
+    }
+
+    fn ty(&self) -> &Type {
+        self.data.ty()
+    }
+
+    fn pin(&self) -> bool {
+        self.data.pin()
+    }
+
+    fn pin_mut(&mut self) -> &mut bool {
+        self.data.pin_mut()
+    }
+
+    fn id(&self) -> u32 {
+        self.data.id()
+    }
+}
+
+impl NodeData for DelayNode<'_> {
+    fn outgoing_family(&self) -> Family {
+        self.family()
+    }
+
+    fn family(&self) -> Family {
+        self.data.family()
+    }
+
+    fn ty(&self) -> &Type {
+        self.data.ty()
+    }
+
+    fn pin(&self) -> bool {
+        self.data.pin()
+    }
+
+    fn pin_mut(&mut self) -> &mut bool {
+        self.data.pin_mut()
+    }
+
+    fn id(&self) -> u32 {
+        self.data.id()
+    }
+}
+
 impl NodeData for ReData {
     fn outgoing_family(&self) -> Family {
         self.family()
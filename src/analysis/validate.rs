@@ -0,0 +1,144 @@
+// validate the finished graph before code generation
+// every error points at the let statement that introduced the node
+
+use std::collections::HashMap;
+
+use petgraph::{
+    algo::tarjan_scc,
+    graph::NodeIndex,
+    visit::{Dfs, EdgeFiltered},
+    Graph,
+};
+use quote::quote;
+use syn::{Error, Result};
+
+use crate::parser::ReLocal;
+
+use super::{is_inferred, is_propagating, DelayNode, NodeData, ReEdge, ReNode};
+
+pub fn validate(
+    graph: &Graph<ReNode, ReEdge>,
+    origins: &HashMap<NodeIndex, &ReLocal>,
+) -> Result<()> {
+    let mut errors = Vec::new();
+    errors.extend(check_dangling(graph, origins));
+    errors.extend(check_cycles(graph, origins));
+    errors.extend(check_reachable(graph, origins));
+    errors.extend(check_types(graph, origins));
+    match errors.into_iter().reduce(|mut all, error| {
+        all.combine(error);
+        all
+    }) {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
+
+fn statement_error(local: &ReLocal, message: impl std::fmt::Display) -> Error {
+    let let_token = &local.let_token;
+    let semi_token = &local.semi_token;
+    Error::new_spanned(quote! { #let_token #semi_token }, message)
+}
+
+/// Delays whose forward reference was never declared.
+fn check_dangling(
+    graph: &Graph<ReNode, ReEdge>,
+    origins: &HashMap<NodeIndex, &ReLocal>,
+) -> Vec<Error> {
+    let mut errors = Vec::new();
+    for idx in graph.node_indices() {
+        let has_input = graph
+            .neighbors_directed(idx, petgraph::Incoming)
+            .next()
+            .is_some();
+        if let ReNode::Delay(DelayNode {
+            forward: Some(ident),
+            ..
+        }) = &graph[idx]
+        {
+            if !has_input {
+                errors.push(statement_error(
+                    origins[&idx],
+                    format!("unknown reactive `{}`", ident),
+                ));
+            }
+        }
+    }
+    errors
+}
+
+/// Cycles are only allowed if they pass through a delay. Only the input of a
+/// delay can be declared later, so this guards the visitor.
+fn check_cycles(
+    graph: &Graph<ReNode, ReEdge>,
+    origins: &HashMap<NodeIndex, &ReLocal>,
+) -> Vec<Error> {
+    let propagation = EdgeFiltered::from_fn(graph, |edge| is_propagating(graph, edge));
+    let mut errors = Vec::new();
+    for component in tarjan_scc(&propagation) {
+        if component.len() < 2 {
+            continue;
+        }
+        // report the cycle once, at the statement declared first
+        let first = component
+            .iter()
+            .min_by_key(|idx| graph[**idx].id())
+            .expect("empty component");
+        errors.push(statement_error(
+            origins[first],
+            "cycle must pass through delay",
+        ));
+    }
+    errors
+}
+
+/// Every reactive has to depend on a source or the initial value of a delay.
+/// Reactives are built from earlier ones, so this guards the visitor as well.
+fn check_reachable(
+    graph: &Graph<ReNode, ReEdge>,
+    origins: &HashMap<NodeIndex, &ReLocal>,
+) -> Vec<Error> {
+    let mut dfs = Dfs::empty(graph);
+    for idx in graph.node_indices() {
+        if let ReNode::Var(_) | ReNode::Evt(_) | ReNode::Delay(_) = graph[idx] {
+            dfs.move_to(idx);
+            while dfs.next(graph).is_some() {}
+        }
+    }
+    let mut errors = Vec::new();
+    let mut reported = Vec::new();
+    for idx in graph.node_indices() {
+        let local = origins[&idx];
+        if dfs.discovered.contains(idx.index()) || reported.contains(&&local.ident) {
+            continue;
+        }
+        reported.push(&local.ident);
+        errors.push(statement_error(
+            local,
+            "reactive does not depend on any source",
+        ));
+    }
+    errors
+}
+
+/// Pinned reactives are stored in the state and need a type rustc can not infer.
+fn check_types(
+    graph: &Graph<ReNode, ReEdge>,
+    origins: &HashMap<NodeIndex, &ReLocal>,
+) -> Vec<Error> {
+    let mut errors = Vec::new();
+    let mut reported = Vec::new();
+    for idx in graph.node_indices() {
+        let node = &graph[idx];
+        let local = origins[&idx];
+        if !node.pin() || !is_inferred(node.ty()) || reported.contains(&&local.ident) {
+            continue;
+        }
+        reported.push(&local.ident);
+        errors.push(statement_error(
+            local,
+            "type annotations needed for pinned reactive, add a closure return type or `::<T>`",
+        ));
+    }
+    errors
+}
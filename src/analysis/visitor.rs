@@ -2,18 +2,26 @@
 // check incoming and outgoing types
 // prefix for anonymous reactives
 
-use crate::parser::{ReBlock, ReClosure, ReExpr, ReLocal};
+use crate::parser::{ReBlock, ReClosure, ReExpr, ReFunction, ReLocal};
 use petgraph::{graph::NodeIndex, Graph};
-use syn::{Error, Result, Type};
+use proc_macro2::{Delimiter, Spacing, Span, TokenStream, TokenTree};
+use quote::quote;
+use std::{collections::HashMap, ptr};
+use syn::{parse_quote, Error, Pat, Result, Type};
 
 use super::{
-    ChangedNode, EvtNode, Family, FilterNode, FoldNode, MapNode, NameNode, NodeData, ReData,
-    ReEdge, ReNode, VarNode,
+    inferred_type, is_inferred, mismatched, ChangedNode, DelayNode, EvtNode, Family, FilterNode,
+    FoldNode, HoldNode, MapNode, MergeNode, NameNode, NodeData, ReData, ReEdge, ReNode, Role,
+    SnapshotNode, VarNode,
 };
 
 pub struct ReVisitor<'ast> {
     pub graph: Graph<ReNode<'ast>, ReEdge>,
+    pub origins: HashMap<NodeIndex, &'ast ReLocal>,
+    /// Nodes pinned by the analysis instead of `pin`
+    pub warnings: Vec<(Span, String)>,
     name_nodes: Vec<(NameNode<'ast>, NodeIndex)>,
+    current_local: Option<&'ast ReLocal>,
     node_count: u32,
 }
 
@@ -31,9 +39,44 @@ impl<'ast> ReVisitor<'ast> {
         for local in &i.stmts {
             self.visit_relocal(local)?;
         }
+        self.resolve_forward_delays()
+    }
+    /// Connect delays to inputs declared after them. Inputs that are never
+    /// declared stay dangling and are reported by the validation pass.
+    fn resolve_forward_delays(&mut self) -> Result<()> {
+        let delays: Vec<_> = self
+            .graph
+            .node_indices()
+            .filter_map(|idx| match &self.graph[idx] {
+                ReNode::Delay(DelayNode {
+                    forward: Some(ident),
+                    data,
+                    ..
+                }) => Some((idx, *ident, data.ty.clone())),
+                _ => None,
+            })
+            .collect();
+        for (delay_idx, ident, ty) in delays {
+            let (name, name_idx) = match self.name_nodes.iter().find(|(n, _)| *ident == n.id.ident)
+            {
+                Some((name, idx)) => (name, *idx),
+                None => continue,
+            };
+            if name.family() != Family::Variable {
+                return Err(Error::new(ident.span(), "delay only valid on variables"));
+            }
+            if mismatched(name.ty(), &ty) {
+                return Err(Error::new(
+                    ident.span(),
+                    "type of delayed reactive does not match annotation",
+                ));
+            }
+            self.graph.add_edge(name_idx, delay_idx, ReEdge::new(ty));
+        }
         Ok(())
     }
     fn visit_relocal(&mut self, i: &'ast ReLocal) -> Result<()> {
+        self.current_local = Some(i);
         let (mut last_idxs, last_fam) = self.visit_reexpr(&i.init)?;
         let name = &i.ident;
         let name_str = name.ident.to_string();
@@ -46,12 +89,9 @@ impl<'ast> ReVisitor<'ast> {
         let last_len = last_idxs.len();
         let (last_idx, last_ty) = last_idxs.remove(0);
         let last_node = self.graph.node_weight_mut(last_idx).unwrap();
-        let pin = if i.pin_token.is_some() {
+        let pin = if let Some(pin_token) = i.pin_token {
             if last_len != 1 {
-                return Err(Error::new(
-                    i.pin_token.unwrap().span,
-                    "cannot pin group of reactives",
-                ));
+                return Err(Error::new(pin_token.span, "cannot pin group of reactives"));
             }
             if let ReNode::Name(namenode) = last_node {
                 return Err(Error::new(
@@ -61,10 +101,30 @@ impl<'ast> ReVisitor<'ast> {
             }
             *last_node.pin_mut() = true;
             true
-        } else if last_node.pin() {
+        } else if let Some(observe_token) = i.observe_token {
+            if last_len != 1 {
+                return Err(Error::new(
+                    observe_token.span,
+                    "cannot observe group of reactives",
+                ));
+            }
+            // observers are notified from the state of the original reactive
+            let source_idx = self.resolve_name(last_idx);
+            let source_ident = &self.origins[&source_idx].ident.ident;
+            let source = self.graph.node_weight_mut(source_idx).unwrap();
+            if !source.pin() {
+                *source.pin_mut() = true;
+                self.warnings.push((
+                    name.ident.span(),
+                    format!(
+                        "`{}` is implicitly pinned to be observed by `{}`",
+                        source_ident, name.ident
+                    ),
+                ));
+            }
             true
         } else {
-            false
+            last_node.pin()
         };
         let name_node = NameNode {
             id: name,
@@ -76,9 +136,9 @@ impl<'ast> ReVisitor<'ast> {
             },
         };
         let new_node = ReNode::Name(name_node.clone());
-        let node_idx = self.graph.add_node(new_node);
+        let node_idx = self.add_node(new_node);
         self.name_nodes.push((name_node, node_idx));
-        let edge = ReEdge { ty: last_ty };
+        let edge = ReEdge::new(last_ty);
         self.graph.add_edge(last_idx, node_idx, edge);
         Ok(())
     }
@@ -109,7 +169,7 @@ impl<'ast> ReVisitor<'ast> {
                     initial: &varexpr.expr,
                     data,
                 });
-                let idx = self.graph.add_node(node);
+                let idx = self.add_node(node);
                 Ok((vec![(idx, varexpr.ty.clone())], Family::Variable))
             }
             ReExpr::Evt(evtexpr) => {
@@ -121,7 +181,7 @@ impl<'ast> ReVisitor<'ast> {
                         family: Family::Event,
                     },
                 });
-                let idx = self.graph.add_node(node);
+                let idx = self.add_node(node);
                 Ok((vec![(idx, evtexpr.ty.clone())], Family::Event))
             }
             ReExpr::Ident(identexpr) => {
@@ -136,16 +196,21 @@ impl<'ast> ReVisitor<'ast> {
             }
             ReExpr::Fold(foldexpr) => {
                 let (incoming, fam) = self.visit_reexpr(&foldexpr.left_expr)?;
-                let ty = self.visit_reclosure(&foldexpr.closure)?.clone();
                 if fam != Family::Event {
                     return Err(Error::new(
                         foldexpr.fold_token.span,
                         "incoming node must be event",
                     ));
                 }
+                let ty = function_type(&foldexpr.function, foldexpr.ty.as_ref())?;
+                // the accumulator is passed by value in front of the inputs
+                let expected = std::iter::once(ty.clone())
+                    .chain(incoming.iter().map(|(_, ty)| reference(ty)))
+                    .collect::<Vec<_>>();
+                self.visit_refunction(&foldexpr.function, &expected)?;
                 let node = ReNode::Fold(FoldNode {
                     initial: &foldexpr.init_expr,
-                    update_expr: &foldexpr.closure,
+                    update_expr: &foldexpr.function,
                     data: ReData {
                         ty: ty.clone(),
                         id: self.next_idx(),
@@ -153,18 +218,23 @@ impl<'ast> ReVisitor<'ast> {
                         pin: true,
                     },
                 });
-                let idx = self.graph.add_node(node);
+                let idx = self.add_node(node);
                 for (node, ty) in incoming {
-                    let edge = ReEdge { ty: ty.clone() };
+                    let edge = ReEdge::new(ty.clone());
                     self.graph.add_edge(node, idx, edge);
                 }
                 Ok((vec![(idx, ty)], Family::Variable))
             }
             ReExpr::Map(mapexpr) => {
                 let (incoming, incoming_fam) = self.visit_reexpr(&mapexpr.left_expr)?;
-                let ty = self.visit_reclosure(&mapexpr.closure)?.clone();
+                let expected = incoming
+                    .iter()
+                    .map(|(_, ty)| reference(ty))
+                    .collect::<Vec<_>>();
+                let ty = function_type(&mapexpr.function, mapexpr.ty.as_ref())?;
+                self.visit_refunction(&mapexpr.function, &expected)?;
                 let node = ReNode::Map(MapNode {
-                    update_expr: &mapexpr.closure,
+                    update_expr: &mapexpr.function,
                     data: ReData {
                         pin: false,
                         ty: ty.clone(),
@@ -172,9 +242,9 @@ impl<'ast> ReVisitor<'ast> {
                         family: incoming_fam,
                     },
                 });
-                let idx = self.graph.add_node(node);
+                let idx = self.add_node(node);
                 for (node, ty) in incoming {
-                    let edge = ReEdge { ty: ty.clone() };
+                    let edge = ReEdge::new(ty.clone());
                     self.graph.add_edge(node, idx, edge);
                 }
 
@@ -182,7 +252,6 @@ impl<'ast> ReVisitor<'ast> {
             }
             ReExpr::Filter(filterexpr) => {
                 let (mut incoming, incoming_fam) = self.visit_reexpr(&filterexpr.left_expr)?;
-                self.visit_reclosure(&filterexpr.closure)?;
                 let (idx, ty) = incoming.remove(0); //first node is output type
                 let node_fam = self.graph.node_weight(idx).unwrap().family();
                 if incoming_fam != Family::Event || node_fam != Family::Event {
@@ -191,8 +260,9 @@ impl<'ast> ReVisitor<'ast> {
                         "filter only valid on events",
                     ));
                 }
+                self.visit_refunction(&filterexpr.function, &[reference(&ty)])?;
                 let node = ReNode::Filter(FilterNode {
-                    filter_expr: &filterexpr.closure,
+                    filter_expr: &filterexpr.function,
                     data: ReData {
                         pin: false,
                         ty: ty.clone(),
@@ -200,10 +270,10 @@ impl<'ast> ReVisitor<'ast> {
                         family: incoming_fam,
                     },
                 });
-                let idx_filter = self.graph.add_node(node);
-                let edge = ReEdge { ty: ty.clone() };
+                let idx_filter = self.add_node(node);
+                let edge = ReEdge::new(ty.clone());
                 self.graph.add_edge(idx, idx_filter, edge);
-                Ok((vec![(idx, ty)], incoming_fam))
+                Ok((vec![(idx_filter, ty)], incoming_fam))
             }
             ReExpr::Changed(changedexpr) => {
                 let (mut incoming, incoming_fam) = self.visit_reexpr(&changedexpr.left_expr)?;
@@ -215,8 +285,26 @@ impl<'ast> ReVisitor<'ast> {
                         "changed only valid on variables",
                     ));
                 }
-                let incoming = self.graph.node_weight_mut(idx).unwrap();
-                *incoming.pin_mut() = true;
+                // changed needs the change flag of the original reactive
+                let source_idx = self.resolve_name(idx);
+                let source_local = self.origins[&source_idx];
+                let incoming = self.graph.node_weight_mut(source_idx).unwrap();
+                if !incoming.pin() {
+                    *incoming.pin_mut() = true;
+                    // an input built in the same statement has no name of its own
+                    let message = match self.current_local {
+                        Some(local) if ptr::eq(local, source_local) => format!(
+                            "input of `changed()` in `{}` is implicitly pinned",
+                            local.ident.ident
+                        ),
+                        _ => format!(
+                            "`{}` is implicitly pinned as input of `changed()`",
+                            source_local.ident.ident
+                        ),
+                    };
+                    self.warnings
+                        .push((changedexpr.changed_token.span, message));
+                }
                 let node = ReNode::Changed(ChangedNode {
                     data: ReData {
                         pin: false,
@@ -225,20 +313,225 @@ impl<'ast> ReVisitor<'ast> {
                         family: Family::Event,
                     },
                 });
-                let idx_changed = self.graph.add_node(node);
-                let edge = ReEdge { ty: ty.clone() };
+                let idx_changed = self.add_node(node);
+                let edge = ReEdge::new(ty.clone());
                 self.graph.add_edge(idx, idx_changed, edge);
-                Ok((vec![(idx, ty)], Family::Event))
+                Ok((vec![(idx_changed, ty)], Family::Event))
             }
+            ReExpr::Merge(mergeexpr) => {
+                let (left, left_fam) = self.visit_reexpr(&mergeexpr.left_expr)?;
+                let (right, right_fam) = self.visit_reexpr(&mergeexpr.right_expr)?;
+                if left.len() != 1 || right.len() != 1 {
+                    return Err(Error::new(
+                        mergeexpr.or_token.spans[0],
+                        "signal group not allowed as input to merge",
+                    ));
+                }
+                if left_fam != Family::Event || right_fam != Family::Event {
+                    return Err(Error::new(
+                        mergeexpr.or_token.spans[0],
+                        "merge only valid on events",
+                    ));
+                }
+                let (left_idx, ty) = left[0].clone();
+                let (right_idx, right_ty) = right[0].clone();
+                if mismatched(&ty, &right_ty) {
+                    return Err(Error::new(
+                        mergeexpr.or_token.spans[0],
+                        "merged events must have the same type",
+                    ));
+                }
+                let ty = if is_inferred(&ty) { right_ty } else { ty };
+                let node = ReNode::Merge(MergeNode {
+                    data: ReData {
+                        pin: false,
+                        ty: ty.clone(),
+                        id: self.next_idx(),
+                        family: Family::Event,
+                    },
+                });
+                let idx_merge = self.add_node(node);
+                let left = ReEdge::with_role(ty.clone(), Role::Left);
+                let right = ReEdge::with_role(ty.clone(), Role::Right);
+                self.graph.add_edge(left_idx, idx_merge, left);
+                self.graph.add_edge(right_idx, idx_merge, right);
+                Ok((vec![(idx_merge, ty)], Family::Event))
+            }
+            ReExpr::Snapshot(snapshotexpr) => {
+                let (mut trigger, trigger_fam) = self.visit_reexpr(&snapshotexpr.left_expr)?;
+                let (mut sampled, sampled_fam) = self.visit_reexpr(&snapshotexpr.var_expr)?;
+                if trigger_fam != Family::Event {
+                    return Err(Error::new(
+                        snapshotexpr.snapshot_token.span,
+                        "snapshot only valid on events",
+                    ));
+                }
+                if sampled_fam != Family::Variable {
+                    return Err(Error::new(
+                        snapshotexpr.snapshot_token.span,
+                        "snapshot can only sample variables",
+                    ));
+                }
+                let (trigger_idx, trigger_ty) = trigger.remove(0);
+                let (sampled_idx, ty) = sampled.remove(0);
+                let node = ReNode::Snapshot(SnapshotNode {
+                    data: ReData {
+                        pin: false,
+                        ty: ty.clone(),
+                        id: self.next_idx(),
+                        family: Family::Event,
+                    },
+                });
+                let idx_snapshot = self.add_node(node);
+                let trigger = ReEdge::with_role(trigger_ty, Role::Trigger);
+                let sampled = ReEdge::with_role(ty.clone(), Role::Sampled);
+                self.graph.add_edge(trigger_idx, idx_snapshot, trigger);
+                self.graph.add_edge(sampled_idx, idx_snapshot, sampled);
+                Ok((vec![(idx_snapshot, ty)], Family::Event))
+            }
+            ReExpr::Hold(holdexpr) => {
+                let (mut incoming, incoming_fam) = self.visit_reexpr(&holdexpr.left_expr)?;
+                if incoming_fam != Family::Event {
+                    return Err(Error::new(
+                        holdexpr.hold_token.span,
+                        "hold only valid on events",
+                    ));
+                }
+                let (idx, ty) = incoming.remove(0);
+                let node = ReNode::Hold(HoldNode {
+                    initial: &holdexpr.init_expr,
+                    data: ReData {
+                        pin: true,
+                        ty: ty.clone(),
+                        id: self.next_idx(),
+                        family: Family::Event,
+                    },
+                });
+                let idx_hold = self.add_node(node);
+                let edge = ReEdge::new(ty.clone());
+                self.graph.add_edge(idx, idx_hold, edge);
+                Ok((vec![(idx_hold, ty)], Family::Variable))
+            }
+            ReExpr::Delay(delayexpr) => {
+                // only the input of delay may refer to a reactive declared later
+                let forward = match &*delayexpr.left_expr {
+                    ReExpr::Ident(identexpr)
+                        if !self
+                            .name_nodes
+                            .iter()
+                            .any(|(n, _)| identexpr.ident == n.id.ident) =>
+                    {
+                        Some(&identexpr.ident)
+                    }
+                    _ => None,
+                };
+                let (incoming, ty) = match forward {
+                    Some(_) => match &delayexpr.ty {
+                        Some(ty) => (None, ty.clone()),
+                        None => return Err(Error::new(
+                            delayexpr.delay_token.span,
+                            "type annotation needed for reactive declared later, use delay::<T>",
+                        )),
+                    },
+                    None => {
+                        let (mut incoming, incoming_fam) =
+                            self.visit_reexpr(&delayexpr.left_expr)?;
+                        if incoming_fam != Family::Variable {
+                            return Err(Error::new(
+                                delayexpr.delay_token.span,
+                                "delay only valid on variables",
+                            ));
+                        }
+                        let (idx, ty) = incoming.remove(0);
+                        match &delayexpr.ty {
+                            Some(annotated) if is_inferred(&ty) => (Some(idx), annotated.clone()),
+                            Some(annotated) if mismatched(annotated, &ty) => {
+                                return Err(Error::new_spanned(
+                                    annotated,
+                                    "type of delayed reactive does not match annotation",
+                                ))
+                            }
+                            _ => (Some(idx), ty),
+                        }
+                    }
+                };
+                let node = ReNode::Delay(DelayNode {
+                    initial: &delayexpr.init_expr,
+                    forward,
+                    data: ReData {
+                        pin: true,
+                        ty: ty.clone(),
+                        id: self.next_idx(),
+                        family: Family::Variable,
+                    },
+                });
+                let idx_delay = self.add_node(node);
+                if let Some(idx) = incoming {
+                    let edge = ReEdge::new(ty.clone());
+                    self.graph.add_edge(idx, idx_delay, edge);
+                }
+                Ok((vec![(idx_delay, ty)], Family::Variable))
+            }
+        }
+    }
+    /// Follow name nodes back to the reactive they refer to.
+    fn resolve_name(&self, mut idx: NodeIndex) -> NodeIndex {
+        while let Some(ReNode::Name(_)) = self.graph.node_weight(idx) {
+            idx = self
+                .graph
+                .neighbors_directed(idx, petgraph::Incoming)
+                .next()
+                .expect("name node without source");
         }
+        idx
+    }
+    fn add_node(&mut self, node: ReNode<'ast>) -> NodeIndex {
+        let idx = self.graph.add_node(node);
+        let local = self.current_local.expect("node outside of let statement");
+        self.origins.insert(idx, local);
+        idx
     }
     fn next_idx(&mut self) -> u32 {
         let res = self.node_count;
         self.node_count += 1;
         res
     }
-    fn visit_reclosure(&mut self, i: &'ast ReClosure) -> Result<&'ast Type> {
-        Ok(&i.return_type)
+    /// Function paths are checked by rustc against the generated call.
+    fn visit_refunction(&mut self, i: &'ast ReFunction, expected: &[Type]) -> Result<()> {
+        match i {
+            ReFunction::Closure(closure) => self.visit_reclosure(closure, expected),
+            ReFunction::Path(_) => Ok(()),
+        }
+    }
+    /// Check the annotated closure parameters against the expected argument types.
+    fn visit_reclosure(&mut self, i: &'ast ReClosure, expected: &[Type]) -> Result<()> {
+        if i.inputs.len() != expected.len() {
+            let or1_token = &i.or1_token;
+            let or2_token = &i.or2_token;
+            return Err(Error::new_spanned(
+                quote! { #or1_token #or2_token },
+                format!(
+                    "expected {} closure parameter{}, found {}",
+                    expected.len(),
+                    if expected.len() == 1 { "" } else { "s" },
+                    i.inputs.len()
+                ),
+            ));
+        }
+        for (input, expected_ty) in i.inputs.iter().zip(expected) {
+            if let Pat::Type(pat_type) = input {
+                if mismatched(&pat_type.ty, expected_ty) {
+                    return Err(Error::new_spanned(
+                        &pat_type.ty,
+                        format!(
+                            "mismatched parameter type, expected `{}`",
+                            type_name(expected_ty)
+                        ),
+                    ));
+                }
+            }
+        }
+        Ok(())
     }
     pub fn reactive_graph(self) -> Graph<ReNode<'ast>, ReEdge> {
         self.graph
@@ -246,8 +539,86 @@ impl<'ast> ReVisitor<'ast> {
     pub fn new() -> Self {
         Self {
             graph: Graph::new(),
+            origins: HashMap::new(),
+            warnings: Vec::new(),
             name_nodes: Vec::new(),
+            current_local: None,
             node_count: 0u32,
         }
     }
 }
+
+/// Output type of a function, either from the closure signature or from a
+/// turbofish. Without both the type is left to rustc.
+fn function_type(function: &ReFunction, turbofish: Option<&Type>) -> Result<Type> {
+    let return_type = match function {
+        ReFunction::Closure(closure) => &closure.return_type,
+        ReFunction::Path(_) => &None,
+    };
+    match (return_type, turbofish) {
+        (Some(return_type), Some(ty)) if mismatched(return_type, ty) => Err(Error::new_spanned(
+            ty,
+            format!(
+                "type does not match closure return type `{}`",
+                type_name(return_type)
+            ),
+        )),
+        (Some(ty), _) | (None, Some(ty)) => Ok(ty.clone()),
+        (None, None) => Ok(inferred_type()),
+    }
+}
+
+/// Reactive values are passed to closures by reference.
+fn reference(ty: &Type) -> Type {
+    parse_quote! { &#ty }
+}
+
+/// Render a type for diagnostics like rustc does, spaces only separate words
+/// and follow commas.
+fn type_name(ty: &Type) -> String {
+    let mut name = String::new();
+    write_tokens(quote! { #ty }, &mut name);
+    name.trim_end().to_string()
+}
+
+fn write_tokens(tokens: TokenStream, name: &mut String) {
+    let mut word = false;
+    for token in tokens {
+        match &token {
+            TokenTree::Ident(_) | TokenTree::Literal(_) => {
+                if word {
+                    name.push(' ');
+                }
+                name.push_str(&token.to_string());
+                word = true;
+                continue;
+            }
+            TokenTree::Punct(punct) => match punct.as_char() {
+                ',' | ';' => {
+                    name.push(punct.as_char());
+                    name.push(' ');
+                }
+                '+' | '=' => {
+                    name.push(' ');
+                    name.push(punct.as_char());
+                    name.push(' ');
+                }
+                '-' if punct.spacing() == Spacing::Joint => name.push_str(" -"),
+                '>' if name.ends_with(" -") => name.push_str("> "),
+                other => name.push(other),
+            },
+            TokenTree::Group(group) => {
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::Brace => ("{", "}"),
+                    Delimiter::None => ("", ""),
+                };
+                name.push_str(open);
+                write_tokens(group.stream(), name);
+                name.push_str(close);
+            }
+        }
+        word = false;
+    }
+}
@@ -1,19 +1,28 @@
+use std::ops::Deref;
+
 use enum_dispatch::enum_dispatch;
-use proc_macro2::{Ident, TokenStream};
+use proc_macro2::{Ident, Span, TokenStream};
 use quote::format_ident;
-use quote::quote;
+use quote::{quote, quote_spanned};
 
-use crate::analysis::{Family, NameNode, NodeData, ReEdge, ReNode};
-use petgraph::{graph::NodeIndex, visit::Topo, Graph};
+use crate::analysis::{is_propagating, Family, NameNode, NodeData, ReEdge, ReNode, Role};
+use crate::parser::{ModHeader, ProgramHeader, ReBlock, ReHeader};
+use petgraph::{
+    graph::NodeIndex,
+    visit::{EdgeFiltered, EdgeRef, Topo},
+    Graph,
+};
 
 mod reactives;
 mod sources;
 
-pub fn generate(graph: &Graph<ReNode, ReEdge>) -> TokenStream {
-    let mut topo_visitor = Topo::new(graph);
+pub fn generate(graph: &Graph<ReNode, ReEdge>, block: &ReBlock) -> TokenStream {
+    let propagation = EdgeFiltered::from_fn(graph, |edge| is_propagating(graph, edge));
+    let mut topo_visitor = Topo::new(&propagation);
     let mut tks_state = TokenStream::new();
     let mut tks_function = TokenStream::new();
     let mut tks_update = TokenStream::new();
+    let mut tks_update_finish = TokenStream::new();
     let mut tks_observers = TokenStream::new();
     let mut tks_input_struct = TokenStream::new();
     let mut tks_notify = TokenStream::new();
@@ -21,14 +30,26 @@ pub fn generate(graph: &Graph<ReNode, ReEdge>) -> TokenStream {
     let mut tks_slots = TokenStream::new();
     let mut tks_sink_fn = TokenStream::new();
     let mut tks_input_fn = TokenStream::new();
-    let mut tks_slot_check = quote! {true};
+    let mut tks_slot_check = TokenStream::new();
     let mut tks_take_all = TokenStream::new();
     let mut tks_slot_init = TokenStream::new();
     let mut tks_initialize = TokenStream::new();
+    let mut tks_initialize_finish = TokenStream::new();
     let mut tks_initialize_struct = TokenStream::new();
     let mut tks_observer_init = TokenStream::new();
-    while let Some(nodeidx) = topo_visitor.next(graph) {
-        let incoming = &get_incoming_weights(graph, nodeidx);
+    let mut tks_stream_fn = TokenStream::new();
+    let mut tks_merge_input = TokenStream::new();
+    let mut tks_check_event = quote! {false};
+    let mut tks_transaction_fn = TokenStream::new();
+    let mut tks_unsubscribe = TokenStream::new();
+    let mut tks_view_fn = TokenStream::new();
+    let mut tks_journal_write = TokenStream::new();
+    let mut tks_notify_changed = TokenStream::new();
+    let mut tks_clear_events = TokenStream::new();
+    let mut tks_journal_read = TokenStream::new();
+    let mut tks_script_fn = TokenStream::new();
+    while let Some(nodeidx) = topo_visitor.next(&propagation) {
+        let incoming = &get_incoming(graph, nodeidx);
         let weight = graph.node_weight(nodeidx).expect("expect valid node index");
         let tokens = weight.generate_interface(incoming);
         tks_card_structs.extend(tokens.card_struct);
@@ -42,47 +63,201 @@ pub fn generate(graph: &Graph<ReNode, ReEdge>) -> TokenStream {
         tks_input_struct.extend(tokens.input_struct_part);
         tks_function.extend(tokens.functions);
         tks_update.extend(tokens.update_part);
+        tks_update_finish.extend(tokens.update_finish);
         tks_notify.extend(tokens.notify_part);
         tks_observers.extend(tokens.observer_struct);
         tks_initialize.extend(tokens.initialize);
+        tks_initialize_finish.extend(tokens.initialize_finish);
         tks_initialize_struct.extend(tokens.initialize_struct);
         tks_observer_init.extend(tokens.initialize_observers);
+        tks_merge_input.extend(tokens.merge_input);
+        tks_check_event.extend(tokens.check_event);
+        tks_transaction_fn.extend(tokens.transaction_fn);
+        tks_unsubscribe.extend(tokens.unsubscribe);
+        tks_view_fn.extend(tokens.view_fn);
+        tks_journal_write.extend(tokens.journal_write);
+        tks_notify_changed.extend(tokens.notify_changed);
+        tks_clear_events.extend(tokens.clear_event);
+        tks_journal_read.extend(tokens.journal_read);
+        tks_script_fn.extend(tokens.script_fn);
+        if block.is_futures() {
+            tks_stream_fn.extend(tokens.stream_fn);
+        }
     }
-    quote! {
-        use std::rc::Rc;
-        use std::rc::Weak;
-        use std::cell::RefCell;
-        use std::sync::mpsc::*;
+    let (shared, tks_prelude) = prelude(block.is_sync());
+    let Transport {
+        prelude: tks_transport,
+        send: tks_send,
+        streams: tks_streams,
+        program_fn: tks_program_fn,
+        sink_impl: tks_sink_impl,
+        empty: tks_empty,
+        recv: tks_recv,
+    } = transport(block.is_futures());
+    let Serde {
+        derive: tks_serde_derive,
+        skip: tks_serde_skip,
+        event: tks_serde_event,
+    } = serde(block.is_serde());
+    let Journal {
+        field: tks_journal_field,
+        init: tks_journal_init,
+        append: tks_journal_append,
+        program_fn: tks_journal_fn,
+        input: tks_journal_input,
+    } = journal(
+        block.is_journal(),
+        block.is_sync(),
+        tks_journal_write,
+        tks_journal_read,
+    );
+    let Script {
+        items: tks_script,
+        program_fn: tks_script_program_fn,
+    } = script(block.is_script(), tks_script_fn);
+    let program = quote! {
+        #tks_prelude
+        #tks_transport
         use std::mem;
         use if_chain::if_chain;
 
         #[derive(Clone)]
+        #tks_serde_derive
         struct Variable<T> {
             value: T,
+            #tks_serde_skip
             change: bool,
         }
 
-        #[derive(Clone)]
+        #[derive(Clone, Copy)]
         enum Event<T> {
             Some(T),
             None,
         }
 
+        #tks_serde_event
+
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum RunStatus {
+            Processed,
+            Empty,
+            Disconnected,
+        }
+
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum Coalesce {
+            KeepEvents,
+            MergeEvents,
+        }
+
         #[derive(Clone)]
+        #tks_serde_derive
         pub struct State {
             #tks_state
         }
 
+        impl State {
+            /// Events only fire in the update they were sent in.
+            fn clear_events(&mut self) {
+                #tks_clear_events
+            }
+        }
+
+        struct Subscribers<T> {
+            observers: Vec<Observer<T>>,
+            callbacks: Vec<(u64, Callback<T>)>,
+            #tks_streams
+        }
+
+        impl<T> Subscribers<T> {
+            fn remove(&mut self, ids: &[u64]) {
+                self.callbacks.retain(|(id, _)| !ids.contains(id));
+            }
+        }
+
+        #[derive(Clone, Copy)]
+        pub struct StateView<'a> {
+            state: &'a State,
+        }
+
+        impl<'a> StateView<'a> {
+            pub fn new(state: &'a State) -> Self {
+                Self { state }
+            }
+
+            #tks_view_fn
+        }
+
         #[derive(Default)]
         struct Observers {
             #tks_observers
         }
 
+        impl Observers {
+            fn remove(&mut self, ids: &[u64]) {
+                #tks_unsubscribe
+            }
+        }
+
+        #[derive(Default)]
+        struct Subscriptions {
+            next_id: u64,
+            cancelled: #shared<Cancelled>,
+        }
+
+        #[must_use = "dropping a Subscription unsubscribes the closure"]
+        pub struct Subscription {
+            id: u64,
+            cancelled: Option<Weak<Cancelled>>,
+        }
+
+        impl Drop for Subscription {
+            fn drop(&mut self) {
+                if let Some(cancelled) = self.cancelled.take().as_ref().and_then(Weak::upgrade) {
+                    cancel(&cancelled, self.id);
+                }
+            }
+        }
+
         pub struct Program {
             state: State,
             observers: Observers,
+            subscriptions: Subscriptions,
             receiver: Receiver<Input>,
-            sink: Sink,
+            sink: Option<Sink>,
+            tick: usize,
+            history: History,
+            #tks_journal_field
+        }
+
+        #[derive(Default)]
+        struct History {
+            capacity: usize,
+            states: std::collections::VecDeque<(usize, State)>,
+        }
+
+        impl History {
+            fn reset(&mut self, tick: usize, state: &State) {
+                self.states.clear();
+                self.record(tick, state);
+            }
+
+            fn record(&mut self, tick: usize, state: &State) {
+                if self.capacity == 0 {
+                    return;
+                }
+                while self.states.back().map_or(false, |(last, _)| *last >= tick) {
+                    self.states.pop_back();
+                }
+                self.states.push_back((tick, state.clone()));
+                while self.states.len() > self.capacity {
+                    self.states.pop_front();
+                }
+            }
+
+            fn get(&self, tick: usize) -> Option<&State> {
+                self.states.iter().find(|(t, _)| *t == tick).map(|(_, state)| state)
+            }
         }
 
         #[derive(Default, Clone)]
@@ -92,8 +267,20 @@ pub fn generate(graph: &Graph<ReNode, ReEdge>) -> TokenStream {
 
         impl Input {
             #tks_input_fn
+
+            fn merge(&mut self, other: Input) {
+                #tks_merge_input
+            }
+
+            fn has_event(&self) -> bool {
+                #tks_check_event
+            }
         }
 
+        #tks_journal_input
+
+        #tks_script
+
         struct Phantom {}
 
         #tks_card_structs
@@ -114,7 +301,7 @@ pub fn generate(graph: &Graph<ReNode, ReEdge>) -> TokenStream {
         pub struct Sink {
             slots: Slots,
             channel_sender: Sender<Input>,
-            id: Rc<Phantom>,
+            id: #shared<Phantom>,
         }
 
         impl Clone for Sink {
@@ -131,60 +318,255 @@ pub fn generate(graph: &Graph<ReNode, ReEdge>) -> TokenStream {
             }
 
             pub fn send(&mut self, input: Input) {
-                if #tks_slot_check
-                {
-                    self.channel_sender.send(input).unwrap();
-                } else
-                {
-                    panic!("Slot empty or from another program instance");
+                if let Err(error) = self.try_send(input) {
+                    panic!("{}", error);
                 }
             }
+            pub fn try_send(&mut self, input: Input) -> Result<(), SinkError> {
+                self.check(&input)?;
+                self.enqueue(input)
+            }
+            fn enqueue(&mut self, input: Input) -> Result<(), SinkError> {
+                #tks_send.map_err(|_| SinkError::Disconnected)
+            }
+            fn check(&self, input: &Input) -> Result<(), SinkError> {
+                #tks_slot_check
+                Ok(())
+            }
+            pub fn transaction(&mut self) -> Transaction<'_> {
+                Transaction { sink: self, input: Input::default() }
+            }
+            fn owns(&self, data: &Weak<Phantom>) -> bool {
+                #shared::downgrade(&self.id).ptr_eq(data)
+            }
             fn new(sender: Sender<Input>) -> Self {
-                let id = Rc::new(Phantom {});
+                let id = #shared::new(Phantom {});
                 Self {
-                    slots: Slots::new(Rc::downgrade(&id)),
+                    slots: Slots::new(#shared::downgrade(&id)),
                     channel_sender: sender,
                     id,
                 }
             }
         }
 
+        #tks_sink_impl
+
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        pub enum SinkError {
+            MissingCard(&'static str),
+            ForeignCard(&'static str),
+            Disconnected,
+        }
+
+        impl std::fmt::Display for SinkError {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                    SinkError::MissingCard(name) => write!(f, "sink holds no card for `{}`", name),
+                    SinkError::ForeignCard(name) => {
+                        write!(f, "card for `{}` belongs to another program instance", name)
+                    }
+                    SinkError::Disconnected => write!(f, "program has been dropped"),
+                }
+            }
+        }
+
+        impl std::error::Error for SinkError {}
+
+        pub struct Transaction<'a> {
+            sink: &'a mut Sink,
+            input: Input,
+        }
+
+        impl Transaction<'_> {
+            #tks_transaction_fn
+
+            pub fn commit(self) -> Result<(), SinkError> {
+                self.sink.try_send(self.input)
+            }
+        }
+
         impl Default for State {
             fn default() -> Self {
                 Program::default_state()
             }
         }
 
+        // closure signatures mirror the reactive types
+        #[allow(clippy::ptr_arg)]
         impl Program {
             pub fn update(state: &mut State, mut inputs: Input) {
                 #tks_update
+                #tks_update_finish
             }
 
             fn notify(observers: &mut Observers, state: &mut State) {
                 #tks_notify
             }
 
+            fn notify_changed(observers: &mut Observers, state: &State, previous: &State) {
+                #tks_notify_changed
+            }
+
+
             pub fn default_state() -> State {
                 #tks_initialize
+                #tks_initialize_finish
                 State { #tks_initialize_struct }
             }
 
-            pub fn run(&mut self) {
-                let Program {state, observers, receiver, sink} = self;
-                let result = receiver.try_recv();
-                match result {
+            fn process(&mut self, inputs: Input) {
+                #tks_journal_append
+                Self::update(&mut self.state, inputs);
+                self.prune();
+                Self::notify(&mut self.observers, &mut self.state);
+                self.tick += 1;
+                self.history.record(self.tick, &self.state);
+            }
+
+            #tks_journal_fn
+
+            #tks_script_program_fn
+
+            pub fn snapshot(&self) -> State {
+                self.state.clone()
+            }
+
+            pub fn restore(&mut self, state: State) {
+                self.state = state;
+                // events of the tick the state was taken in already fired
+                self.state.clear_events();
+                self.history.reset(self.tick, &self.state);
+                self.init();
+            }
+
+            pub fn tick(&self) -> usize {
+                self.tick
+            }
+
+            pub fn keep_history(&mut self, capacity: usize) {
+                self.history.capacity = capacity;
+                self.history.reset(self.tick, &self.state);
+            }
+
+            pub fn goto(&mut self, tick: usize) -> bool {
+                let mut state = match self.history.get(tick) {
+                    Some(state) => state.clone(),
+                    None => return false,
+                };
+                state.clear_events();
+                let previous = mem::replace(&mut self.state, state);
+                self.tick = tick;
+                self.prune();
+                Self::notify_changed(&mut self.observers, &self.state, &previous);
+                true
+            }
+
+            pub fn undo(&mut self) -> bool {
+                self.tick > 0 && self.goto(self.tick - 1)
+            }
+
+            pub fn redo(&mut self) -> bool {
+                self.goto(self.tick + 1)
+            }
+
+            pub fn view(&self) -> StateView<'_> {
+                StateView::new(&self.state)
+            }
+
+            fn prune(&mut self) {
+                let ids = take_cancelled(&self.subscriptions.cancelled);
+                if !ids.is_empty() {
+                    self.observers.remove(&ids);
+                }
+            }
+
+            fn subscription(&mut self) -> Subscription {
+                let id = self.subscriptions.next_id;
+                self.subscriptions.next_id += 1;
+                Subscription { id, cancelled: Some(#shared::downgrade(&self.subscriptions.cancelled)) }
+            }
+
+            pub fn unsubscribe(&mut self, mut subscription: Subscription) {
+                let own = #shared::downgrade(&self.subscriptions.cancelled);
+                if subscription.cancelled.as_ref().map_or(false, |cancelled| cancelled.ptr_eq(&own)) {
+                    subscription.cancelled = None;
+                    self.observers.remove(&[subscription.id]);
+                }
+            }
+
+            fn try_receive(&mut self) -> Result<Input, RunStatus> {
+                match self.receiver.try_recv() {
+                    Ok(inputs) => Ok(inputs),
+                    Err(#tks_empty) => Err(RunStatus::Empty),
+                    Err(_) => Err(RunStatus::Disconnected),
+                }
+            }
+
+            pub fn run(&mut self) -> RunStatus {
+                match self.try_receive() {
                     Ok(inputs) => {
-                        Self::update(state, inputs);
-                        Self::notify(observers, state);
+                        self.process(inputs);
+                        RunStatus::Processed
+                    }
+                    Err(status) => status,
+                }
+            }
+
+            pub fn run_all(&mut self) -> RunStatus {
+                loop {
+                    match self.run() {
+                        RunStatus::Processed => {}
+                        status => return status,
                     }
-                    Err(recv_error) => {
-                        println!("Queue error: {:?}", recv_error);
+                }
+            }
+
+            pub fn run_coalesced(&mut self, mode: Coalesce) -> RunStatus {
+                let mut pending: Option<Input> = None;
+                let status = loop {
+                    match self.try_receive() {
+                        Ok(inputs) => {
+                            let merged = match pending.take() {
+                                Some(mut merged) => {
+                                    merged.merge(inputs);
+                                    merged
+                                }
+                                None => inputs,
+                            };
+                            if mode == Coalesce::KeepEvents && merged.has_event() {
+                                self.process(merged);
+                            } else {
+                                pending = Some(merged);
+                            }
+                        }
+                        Err(status) => break status,
                     }
+                };
+                if let Some(inputs) = pending {
+                    self.process(inputs);
+                }
+                status
+            }
+
+            pub fn run_blocking(&mut self) -> RunStatus {
+                // nothing can be queued before the sink is taken, and waiting
+                // on the sender held by the program would never return
+                if self.sink.is_some() {
+                    return RunStatus::Empty;
+                }
+                let receiver = &mut self.receiver;
+                match #tks_recv {
+                    Some(inputs) => {
+                        self.process(inputs);
+                        RunStatus::Processed
+                    }
+                    None => RunStatus::Disconnected,
                 }
             }
 
             pub fn init(&mut self) {
-                let Program { state, observers, receiver, sink } = self;
+                self.prune();
+                let Program { state, observers, .. } = self;
                 #tks_observer_init
                 Self::notify(observers, state);
             }
@@ -194,36 +576,627 @@ pub fn generate(graph: &Graph<ReNode, ReEdge>) -> TokenStream {
                 Self {
                     state: State::default(),
                     observers: Observers::default(),
+                    subscriptions: Subscriptions::default(),
                     receiver: recv,
-                    sink: Sink::new(send),
+                    sink: Some(Sink::new(send)),
+                    tick: 0,
+                    history: History::default(),
+                    #tks_journal_init
                 }
             }
 
             pub fn sink(&mut self) -> Sink {
-                let mut new_sink = self.sink.clone();
-                new_sink.take_all(&mut self.sink);
-                new_sink
+                self.sink.take().expect("sink already taken, clone it instead")
             }
 
+            #tks_program_fn
+
             #tks_function
+            #tks_stream_fn
         }
+    };
+    wrap_program(program, block)
+}
+
+/// Pointer type for card identity and subscriptions, and the observer types of
+/// the program. Observers of a sync program are `Send`, which makes `Sink` and
+/// `Program` `Send` as well.
+fn prelude(sync: bool) -> (Ident, TokenStream) {
+    if sync {
+        let prelude = quote! {
+            use std::sync::{Arc, Mutex, Weak};
+
+            type Observer<T> = Weak<Mutex<dyn FnMut(&T) + Send>>;
+            type Callback<T> = Box<dyn FnMut(&T) + Send>;
+            type Cancelled = Mutex<Vec<u64>>;
+
+            pub trait ObserverFn<T>: FnMut(&T) + Send + 'static {}
+            impl<T, F: FnMut(&T) + Send + 'static> ObserverFn<T> for F {}
+
+            fn call_observer<T>(cb: &Mutex<dyn FnMut(&T) + Send>, value: &T) {
+                (&mut *cb.lock().expect("observer panicked"))(value);
+            }
+
+            fn cancel(cancelled: &Cancelled, id: u64) {
+                cancelled.lock().expect("observer panicked").push(id);
+            }
+
+            fn take_cancelled(cancelled: &Cancelled) -> Vec<u64> {
+                mem::take(&mut *cancelled.lock().expect("observer panicked"))
+            }
+        };
+        (format_ident!("Arc"), prelude)
+    } else {
+        let prelude = quote! {
+            use std::rc::{Rc, Weak};
+            use std::cell::RefCell;
+
+            type Observer<T> = Weak<RefCell<dyn FnMut(&T)>>;
+            type Callback<T> = Box<dyn FnMut(&T)>;
+            type Cancelled = RefCell<Vec<u64>>;
+
+            pub trait ObserverFn<T>: FnMut(&T) + 'static {}
+            impl<T, F: FnMut(&T) + 'static> ObserverFn<T> for F {}
+
+            fn call_observer<T>(cb: &RefCell<dyn FnMut(&T)>, value: &T) {
+                (&mut *cb.borrow_mut())(value);
+            }
+
+            fn cancel(cancelled: &Cancelled, id: u64) {
+                cancelled.borrow_mut().push(id);
+            }
+
+            fn take_cancelled(cancelled: &Cancelled) -> Vec<u64> {
+                mem::take(&mut *cancelled.borrow_mut())
+            }
+        };
+        (format_ident!("Rc"), prelude)
     }
 }
-fn get_incoming_weights<'ast>(
+
+/// With `#![serde]` the state can be serialized. Events only live
+/// for one update and are always restored as `Event::None`, the same goes for
+/// change flags.
+struct Serde {
+    derive: TokenStream,
+    skip: TokenStream,
+    event: TokenStream,
+}
+
+fn serde(serde: bool) -> Serde {
+    if serde {
+        Serde {
+            derive: quote! {
+                #[derive(serde::Serialize, serde::Deserialize)]
+            },
+            skip: quote! {
+                #[serde(skip)]
+            },
+            event: quote! {
+                impl<T> serde::Serialize for Event<T> {
+                    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                        serializer.serialize_unit()
+                    }
+                }
+
+                impl<'de, T> serde::Deserialize<'de> for Event<T> {
+                    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                        <() as serde::Deserialize>::deserialize(deserializer)?;
+                        Ok(Event::None)
+                    }
+                }
+            },
+        }
+    } else {
+        Serde {
+            derive: TokenStream::new(),
+            skip: TokenStream::new(),
+            event: TokenStream::new(),
+        }
+    }
+}
+
+/// With `#![journal]` every applied input is appended to a writer as one
+/// line of JSON, which maps the names of the sources set by the input to
+/// their values. The generated code refers to the `serde` and `serde_json`
+/// crates of the caller.
+struct Journal {
+    field: TokenStream,
+    init: TokenStream,
+    append: TokenStream,
+    program_fn: TokenStream,
+    input: TokenStream,
+}
+
+fn journal(journal: bool, sync: bool, write: TokenStream, read: TokenStream) -> Journal {
+    if !journal {
+        return Journal {
+            field: TokenStream::new(),
+            init: TokenStream::new(),
+            append: TokenStream::new(),
+            program_fn: TokenStream::new(),
+            input: TokenStream::new(),
+        };
+    }
+    let send = if sync {
+        quote! { + Send }
+    } else {
+        TokenStream::new()
+    };
+    Journal {
+        field: quote! {
+            journal: Option<Box<dyn std::io::Write #send>>,
+            journal_error: Option<std::io::Error>,
+        },
+        init: quote! {
+            journal: None,
+            journal_error: None,
+        },
+        append: quote! {
+            if let Some(writer) = &mut self.journal {
+                let written = inputs
+                    .to_journal()
+                    .map_err(std::io::Error::from)
+                    .and_then(|line| writeln!(writer, "{}", line));
+                if let Err(error) = written {
+                    self.journal = None;
+                    self.journal_error = Some(error);
+                }
+            }
+        },
+        program_fn: quote! {
+            pub fn set_journal(&mut self, writer: impl std::io::Write #send + 'static) {
+                self.journal = Some(Box::new(writer));
+            }
+
+            pub fn journal_error(&mut self) -> Option<std::io::Error> {
+                self.journal_error.take()
+            }
+
+            pub fn replay(&mut self, reader: impl std::io::BufRead) -> std::io::Result<usize> {
+                let mut ticks = 0;
+                for line in reader.lines() {
+                    let line = line?;
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    self.process(Input::from_journal(&line)?);
+                    ticks += 1;
+                }
+                Ok(ticks)
+            }
+        },
+        input: quote! {
+            impl Input {
+                fn to_journal(&self) -> serde_json::Result<String> {
+                    let mut entry = serde_json::Map::new();
+                    #write
+                    serde_json::to_string(&entry)
+                }
+
+                fn from_journal(line: &str) -> serde_json::Result<Input> {
+                    let mut entry: serde_json::Map<String, serde_json::Value> = serde_json::from_str(line)?;
+                    let mut input = Input::default();
+                    #read
+                    match entry.keys().next() {
+                        Some(key) => Err(serde::de::Error::custom(format!("unknown source `{}`", key))),
+                        None => Ok(input),
+                    }
+                }
+            }
+        },
+    }
+}
+
+/// With `#![script]` `Program::script()` runs a sequence of inputs
+/// tick by tick and compares the values of pinned reactives after each tick
+/// with the expected ones. Mismatches panic with a table of all ticks.
+struct Script {
+    items: TokenStream,
+    program_fn: TokenStream,
+}
+
+fn script(script: bool, script_fn: TokenStream) -> Script {
+    if !script {
+        return Script {
+            items: TokenStream::new(),
+            program_fn: TokenStream::new(),
+        };
+    }
+    Script {
+        items: quote! {
+            pub struct Script<'a> {
+                program: &'a mut Program,
+                ticks: Vec<Input>,
+                input: Option<Input>,
+                columns: Vec<Box<dyn FnOnce(&[State]) -> ScriptColumn>>,
+            }
+
+            impl Script<'_> {
+                #script_fn
+
+                pub fn tick(mut self) -> Self {
+                    let input = self.input.take().unwrap_or_default();
+                    self.ticks.push(input);
+                    self
+                }
+
+                pub fn run(self) -> Vec<State> {
+                    let Script { program, mut ticks, input, columns } = self;
+                    ticks.extend(input);
+                    let first = program.tick + 1;
+                    let mut states = Vec::new();
+                    for input in ticks {
+                        program.process(input);
+                        states.push(program.snapshot());
+                    }
+                    let columns: Vec<ScriptColumn> = columns.into_iter().map(|column| column(&states)).collect();
+                    if columns.iter().any(|column| column.matches.contains(&false)) {
+                        panic!("{}", ScriptColumn::table(first, &columns));
+                    }
+                    states
+                }
+            }
+
+            struct ScriptColumn {
+                name: &'static str,
+                expected: Vec<String>,
+                actual: Vec<String>,
+                matches: Vec<bool>,
+            }
+
+            impl ScriptColumn {
+                fn new<T: PartialEq + std::fmt::Debug>(name: &'static str, expected: Vec<T>, actual: Vec<T>) -> Self {
+                    let ticks = expected.len().max(actual.len());
+                    let matches = (0..ticks)
+                        .map(|tick| expected.get(tick).is_some() && expected.get(tick) == actual.get(tick))
+                        .collect();
+                    let format = |values: Vec<T>| values.iter().map(|value| format!("{:?}", value)).collect();
+                    Self { name, expected: format(expected), actual: format(actual), matches }
+                }
+
+                fn table(first: usize, columns: &[ScriptColumn]) -> String {
+                    let mut rows = vec![(false, vec![String::from("tick")])];
+                    for column in columns {
+                        rows[0].1.push(format!("{} expected", column.name));
+                        rows[0].1.push(format!("{} actual", column.name));
+                    }
+                    let ticks = columns.iter().map(|column| column.matches.len()).max().unwrap_or(0);
+                    let cell = |values: &[String], tick: usize| {
+                        values.get(tick).cloned().unwrap_or_else(|| String::from("-"))
+                    };
+                    for tick in 0..ticks {
+                        let mut row = vec![(first + tick).to_string()];
+                        for column in columns {
+                            row.push(cell(&column.expected, tick));
+                            row.push(cell(&column.actual, tick));
+                        }
+                        let failed = columns.iter().any(|column| column.matches.get(tick) == Some(&false));
+                        rows.push((failed, row));
+                    }
+                    let mut widths = vec![0; rows[0].1.len()];
+                    for (_, row) in &rows {
+                        for (width, cell) in widths.iter_mut().zip(row) {
+                            *width = (*width).max(cell.len());
+                        }
+                    }
+                    let mut table = String::from("script does not match the expected values\n");
+                    for (failed, row) in rows {
+                        let cells: Vec<String> = row
+                            .iter()
+                            .zip(&widths)
+                            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                            .collect();
+                        let marker = if failed { ">" } else { " " };
+                        table.push_str(marker);
+                        table.push(' ');
+                        table.push_str(cells.join(" | ").trim_end());
+                        table.push('\n');
+                    }
+                    table
+                }
+            }
+        },
+        program_fn: quote! {
+            pub fn script(&mut self) -> Script<'_> {
+                Script { program: self, ticks: Vec::new(), input: None, columns: Vec::new() }
+            }
+        },
+    }
+}
+
+/// Generated code that differs between the std channel and the futures
+/// channel, which is selected with `#![futures]`.
+struct Transport {
+    prelude: TokenStream,
+    send: TokenStream,
+    streams: TokenStream,
+    program_fn: TokenStream,
+    sink_impl: TokenStream,
+    empty: TokenStream,
+    recv: TokenStream,
+}
+
+fn transport(futures: bool) -> Transport {
+    let notify_observers = quote! {
+        for (_, callback) in &mut self.callbacks {
+            callback(value);
+        }
+        self.observers.retain(|observer| {
+            if let Some(cb) = Weak::upgrade(observer) {
+                call_observer(&cb, value);
+                true
+            } else {
+                false
+            }
+        });
+    };
+    if futures {
+        Transport {
+            prelude: quote! {
+                use futures::channel::mpsc::{
+                    unbounded as channel, SendError as ChannelError,
+                    UnboundedReceiver as Receiver, UnboundedSender as Sender,
+                };
+                use std::pin::Pin;
+                use std::task::{Context, Poll};
+
+                impl<T> Default for Subscribers<T> {
+                    fn default() -> Self {
+                        Self { observers: Vec::new(), callbacks: Vec::new(), streams: Vec::new() }
+                    }
+                }
+
+                impl<T: Clone> Subscribers<T> {
+                    fn notify(&mut self, value: &T) {
+                        #notify_observers
+                        self.streams.retain(|stream| stream.unbounded_send(value.clone()).is_ok());
+                    }
+
+                    fn stream(&mut self) -> Receiver<T> {
+                        let (sender, receiver) = channel();
+                        self.streams.push(sender);
+                        receiver
+                    }
+                }
+            },
+            send: quote! {
+                self.channel_sender.unbounded_send(input)
+            },
+            streams: quote! {
+                streams: Vec<Sender<T>>,
+            },
+            program_fn: quote! {
+                pub async fn run_async(&mut self) -> RunStatus {
+                    // like run_blocking, nothing can be queued before the sink is taken
+                    if self.sink.is_some() {
+                        return RunStatus::Empty;
+                    }
+                    match futures::StreamExt::next(&mut self.receiver).await {
+                        Some(inputs) => {
+                            self.process(inputs);
+                            RunStatus::Processed
+                        }
+                        None => RunStatus::Disconnected,
+                    }
+                }
+            },
+            sink_impl: quote! {
+                /// Error of `Sink` as a `futures::Sink`, the input was rejected
+                /// by the cards or the channel is closed.
+                #[derive(Debug, Clone, PartialEq, Eq)]
+                pub enum SendError {
+                    Sink(SinkError),
+                    Channel(ChannelError),
+                }
+
+                impl std::fmt::Display for SendError {
+                    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                        match self {
+                            SendError::Sink(error) => error.fmt(f),
+                            SendError::Channel(error) => error.fmt(f),
+                        }
+                    }
+                }
+
+                impl std::error::Error for SendError {}
+
+                impl From<SinkError> for SendError {
+                    fn from(error: SinkError) -> Self {
+                        SendError::Sink(error)
+                    }
+                }
+
+                impl From<ChannelError> for SendError {
+                    fn from(error: ChannelError) -> Self {
+                        SendError::Channel(error)
+                    }
+                }
+
+                impl futures::Sink<Input> for Sink {
+                    type Error = SendError;
+
+                    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), SendError>> {
+                        self.channel_sender.poll_ready(cx).map_err(SendError::from)
+                    }
+
+                    fn start_send(mut self: Pin<&mut Self>, input: Input) -> Result<(), SendError> {
+                        self.check(&input)?;
+                        Ok(self.channel_sender.start_send(input)?)
+                    }
+
+                    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), SendError>> {
+                        Poll::Ready(Ok(()))
+                    }
+
+                    fn poll_close(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), SendError>> {
+                        self.channel_sender.disconnect();
+                        Poll::Ready(Ok(()))
+                    }
+                }
+            },
+            empty: quote! {
+                futures::channel::mpsc::TryRecvError::Empty
+            },
+            recv: quote! {
+                futures::executor::block_on(futures::StreamExt::next(receiver))
+            },
+        }
+    } else {
+        Transport {
+            prelude: quote! {
+                use std::sync::mpsc::{channel, Receiver, Sender};
+
+                impl<T> Default for Subscribers<T> {
+                    fn default() -> Self {
+                        Self { observers: Vec::new(), callbacks: Vec::new() }
+                    }
+                }
+
+                impl<T> Subscribers<T> {
+                    fn notify(&mut self, value: &T) {
+                        #notify_observers
+                    }
+                }
+            },
+            send: quote! {
+                self.channel_sender.send(input)
+            },
+            streams: TokenStream::new(),
+            program_fn: TokenStream::new(),
+            sink_impl: TokenStream::new(),
+            empty: quote! {
+                std::sync::mpsc::TryRecvError::Empty
+            },
+            recv: quote! {
+                receiver.recv().ok()
+            },
+        }
+    }
+}
+
+/// Proc macros cannot emit warnings on stable, so every warning refers to a
+/// deprecated item at the span of the offending code.
+pub fn generate_warnings(warnings: &[(Span, String)]) -> TokenStream {
+    warnings
+        .iter()
+        .map(|(span, message)| {
+            let warning = quote_spanned! {*span=> implicitly_pinned };
+            quote! {
+                const _: () = {
+                    #[deprecated(note = #message)]
+                    #[allow(non_camel_case_types)]
+                    struct implicitly_pinned;
+                    let _ = #warning;
+                };
+            }
+        })
+        .collect()
+}
+
+/// Without a header the items are emitted into the calling module. Otherwise
+/// they are placed in a module of their own, which keeps the `use` items of
+/// the generated code out of the caller's namespace.
+fn wrap_program(program: TokenStream, block: &ReBlock) -> TokenStream {
+    match block.header.as_ref() {
+        None => program,
+        Some(ReHeader::Mod(ModHeader { vis, ident, .. })) => quote! {
+            #vis mod #ident {
+                use super::*;
+                #program
+            }
+        },
+        Some(ReHeader::Program(ProgramHeader { vis, ident, .. })) => {
+            let module = format_ident!("__{}", ident);
+            let state = format_ident!("{}State", ident);
+            let sink = format_ident!("{}Sink", ident);
+            let input = format_ident!("{}Input", ident);
+            let status = format_ident!("{}RunStatus", ident);
+            let coalesce = format_ident!("{}Coalesce", ident);
+            let error = format_ident!("{}SinkError", ident);
+            let transaction = format_ident!("{}Transaction", ident);
+            let subscription = format_ident!("{}Subscription", ident);
+            let view = format_ident!("{}StateView", ident);
+            let script = if block.is_script() {
+                let script = format_ident!("{}Script", ident);
+                quote! { Script as #script, }
+            } else {
+                TokenStream::new()
+            };
+            let send_error = if block.is_futures() {
+                let send_error = format_ident!("{}SendError", ident);
+                quote! { SendError as #send_error, }
+            } else {
+                TokenStream::new()
+            };
+            quote! {
+                #[doc(hidden)]
+                #[allow(non_snake_case)]
+                mod #module {
+                    use super::*;
+                    #program
+                }
+                #vis use #module::{
+                    Program as #ident, State as #state, Sink as #sink, Input as #input,
+                    RunStatus as #status, Coalesce as #coalesce, SinkError as #error,
+                    Transaction as #transaction, Subscription as #subscription,
+                    StateView as #view, #script #send_error
+                };
+            }
+        }
+    }
+}
+fn get_incoming<'ast>(graph: &'ast Graph<ReNode<'ast>, ReEdge>, idx: NodeIndex) -> Incoming<'ast> {
+    let mut incoming = Incoming::default();
+    for edge in graph.edges_directed(idx, petgraph::Incoming) {
+        incoming.nodes.push(resolve_name(graph, edge.source()));
+        incoming.roles.push(edge.weight().role);
+    }
+    incoming
+}
+
+/// The reactive behind a chain of names.
+fn resolve_name<'ast>(
     graph: &'ast Graph<ReNode<'ast>, ReEdge>,
     idx: NodeIndex,
-) -> Vec<&'ast ReNode<'ast>> {
-    let incoming_nodes = graph.neighbors_directed(idx, petgraph::Incoming);
-    let mut weights = Vec::new();
-    for node_idx in incoming_nodes {
-        let node = graph.node_weight(node_idx).expect("invalid node index");
-        if let ReNode::Name(_) = node {
-            weights.extend(get_incoming_weights(graph, node_idx));
-        } else {
-            weights.push(node);
+) -> &'ast ReNode<'ast> {
+    match &graph[idx] {
+        ReNode::Name(_) => {
+            let input = graph
+                .neighbors_directed(idx, petgraph::Incoming)
+                .next()
+                .expect("name without input");
+            resolve_name(graph, input)
         }
+        node => node,
+    }
+}
+
+/// Incoming reactives of a node in reverse edge order, with the role of
+/// their edges.
+#[derive(Default)]
+pub struct Incoming<'ast> {
+    nodes: Vec<&'ast ReNode<'ast>>,
+    roles: Vec<Role>,
+}
+
+impl<'ast> Incoming<'ast> {
+    pub fn operand(&self, role: Role) -> &'ast ReNode<'ast> {
+        let position = self
+            .roles
+            .iter()
+            .position(|other| *other == role)
+            .expect("missing operand");
+        self.nodes[position]
+    }
+}
+
+impl<'ast> Deref for Incoming<'ast> {
+    type Target = [&'ast ReNode<'ast>];
+
+    fn deref(&self) -> &Self::Target {
+        &self.nodes
     }
-    weights
 }
 
 #[derive(Default)]
@@ -238,12 +1211,25 @@ pub struct InterfaceTokens {
     pub slot_init: TokenStream,
     pub functions: TokenStream,
     pub update_part: TokenStream,
+    pub update_finish: TokenStream,
     pub state_struct: TokenStream,
     pub observer_struct: TokenStream,
     pub notify_part: TokenStream,
     pub initialize: TokenStream,
+    pub initialize_finish: TokenStream,
     pub initialize_struct: TokenStream,
     pub initialize_observers: TokenStream,
+    pub stream_fn: TokenStream,
+    pub merge_input: TokenStream,
+    pub check_event: TokenStream,
+    pub transaction_fn: TokenStream,
+    pub unsubscribe: TokenStream,
+    pub view_fn: TokenStream,
+    pub journal_write: TokenStream,
+    pub notify_changed: TokenStream,
+    pub clear_event: TokenStream,
+    pub journal_read: TokenStream,
+    pub script_fn: TokenStream,
 }
 
 pub fn change_prefix(ident: &Ident) -> Ident {
@@ -260,7 +1246,7 @@ pub fn val_prefix(ident: &Ident) -> Ident {
 
 #[enum_dispatch]
 pub trait Generate {
-    fn generate_interface(&self, incoming: &Vec<&ReNode>) -> InterfaceTokens;
+    fn generate_interface(&self, incoming: &Incoming) -> InterfaceTokens;
     fn ident(&self) -> Ident;
 }
 
@@ -269,7 +1255,7 @@ impl Generate for NameNode<'_> {
         self.id.ident.clone()
     }
 
-    fn generate_interface(&self, incoming: &Vec<&ReNode>) -> InterfaceTokens {
+    fn generate_interface(&self, incoming: &Incoming) -> InterfaceTokens {
         let name = self.ident();
         let ty = self.ty();
         let ident = self.ident();
@@ -282,12 +1268,32 @@ impl Generate for NameNode<'_> {
         // save state and generate observers
         if pin {
             ift.observer_struct = quote! {
-                #name: Vec<Weak<RefCell<dyn FnMut(&#ty)>>>,
+                #name: Subscribers<#ty>,
             };
             let observer_ident = format_ident!("observe_{}", ident);
             ift.functions = quote! {
-                pub fn #observer_ident(&mut self, observer: Weak<RefCell<dyn FnMut(&#ty)>>) {
-                    self.observers.#name.push(observer);
+                pub fn #observer_ident(&mut self, observer: Observer<#ty>) {
+                    self.observers.#name.observers.push(observer);
+                }
+            };
+            let subscribe_ident = format_ident!("subscribe_{}", ident);
+            ift.functions.extend(quote! {
+                pub fn #subscribe_ident(&mut self, observer: impl ObserverFn<#ty>) -> Subscription {
+                    let subscription = self.subscription();
+                    self.observers.#name.callbacks.push((subscription.id, Box::new(observer)));
+                    subscription
+                }
+            });
+            ift.unsubscribe = quote! {
+                self.#name.remove(ids);
+            };
+            let stream_ident = format_ident!("stream_{}", ident);
+            let getter_ident = format_ident!("get_{}", ident);
+            let expect_ident = format_ident!("expect_{}", ident);
+            let ident_str = ident.to_string();
+            ift.stream_fn = quote! {
+                pub fn #stream_ident(&mut self) -> Receiver<#ty> {
+                    self.observers.#name.stream()
                 }
             };
 
@@ -295,32 +1301,67 @@ impl Generate for NameNode<'_> {
                 Family::Event => {
                     ift.notify_part = quote! {
                         if let Event::Some(value) = &state.#income {
-                            observers.#ident.retain(|lst| {
-                                if let Some(cb) = Weak::upgrade(lst) {
-                                    (&mut *cb.borrow_mut())(value);
-                                    true
-                                } else {
-                                    false
-                                }
-                            });
+                            observers.#ident.notify(value);
+                        }
+                    };
+                    ift.view_fn = quote! {
+                        pub fn #getter_ident(&self) -> Option<&'a #ty> {
+                            match &self.state.#income {
+                                Event::Some(value) => Some(value),
+                                Event::None => None,
+                            }
+                        }
+                    };
+                    ift.functions.extend(quote! {
+                        pub fn #getter_ident(&self) -> Option<&#ty> {
+                            self.view().#getter_ident()
+                        }
+                    });
+                    ift.script_fn = quote! {
+                        pub fn #expect_ident(mut self, expected: impl IntoIterator<Item = Option<#ty>>) -> Self {
+                            let expected: Vec<Option<#ty>> = expected.into_iter().collect();
+                            self.columns.push(Box::new(move |states: &[State]| {
+                                let expected = expected.iter().map(Option::as_ref).collect();
+                                let actual = states.iter().map(|state| StateView::new(state).#getter_ident()).collect();
+                                ScriptColumn::new(#ident_str, expected, actual)
+                            }));
+                            self
                         }
-                        state.#income = Event::None;
                     };
                 }
                 Family::Variable => {
                     ift.notify_part = quote! {
                         if state.#income.change {
-                            observers.#ident.retain(|lst| {
-                                if let Some(cb) = Weak::upgrade(lst) {
-                                    (&mut *cb.borrow_mut())(&state.#income.value);
-                                    true
-                                } else {
-                                    false
-                                }
-                            });
+                            observers.#ident.notify(&state.#income.value);
                         }
                         state.#income.change = false;
                     };
+                    ift.notify_changed = quote! {
+                        if state.#income.value != previous.#income.value {
+                            observers.#ident.notify(&state.#income.value);
+                        }
+                    };
+                    ift.view_fn = quote! {
+                        pub fn #getter_ident(&self) -> &'a #ty {
+                            &self.state.#income.value
+                        }
+                    };
+                    ift.functions.extend(quote! {
+                        pub fn #getter_ident(&self) -> &#ty {
+                            self.view().#getter_ident()
+                        }
+                    });
+                    ift.script_fn = quote! {
+                        pub fn #expect_ident(mut self, expected: impl IntoIterator<Item = #ty>) -> Self {
+                            let expected: Vec<#ty> = expected.into_iter().collect();
+                            self.columns.push(Box::new(move |states: &[State]| {
+                                let expected = expected.iter().collect();
+                                let actual = states.iter().map(|state| StateView::new(state).#getter_ident()).collect();
+                                ScriptColumn::new(#ident_str, expected, actual)
+                            }));
+                            self
+                        }
+                    };
                 }
             }
         }
@@ -336,6 +1377,7 @@ impl Generate for NameNode<'_> {
                 };
                 let card_name = format_ident!("Card{}", name);
                 ift.card_struct = quote! {
+                    #[allow(non_camel_case_types)]
                     pub struct #card_name {
                         data: Weak<Phantom>,
 
@@ -348,24 +1390,37 @@ impl Generate for NameNode<'_> {
                     #name: Some(#card_name {data: data.clone()}),
                 };
                 let push_card = format_ident!("push_{}", name);
+                let try_push_card = format_ident!("try_push_{}", name);
                 let pull_card = format_ident!("pull_{}", name);
                 let send_single = format_ident!("send_{}", name);
+                let try_send_single = format_ident!("try_send_{}", name);
+                let name_str = name.to_string();
                 ift.sink_fn = quote! {
                     pub fn #push_card(&mut self, card: #card_name) {
-                        let id_self = Rc::downgrade(&self.id);
-                        if id_self.ptr_eq(&card.data) {
+                        if let Err(error) = self.#try_push_card(card) {
+                            panic!("{}", error);
+                        }
+                    }
+                    pub fn #try_push_card(&mut self, card: #card_name) -> Result<(), SinkError> {
+                        if self.owns(&card.data) {
                             self.slots.#name = Some(card);
+                            Ok(())
                         } else {
-                            panic!("Card has incorrect id");
+                            Err(SinkError::ForeignCard(#name_str))
                         }
                     }
                     pub fn #pull_card(&mut self) -> Option<#card_name> {
                         self.slots.#name.take()
                     }
                     pub fn #send_single(&mut self, value: #ty) {
+                        if let Err(error) = self.#try_send_single(value) {
+                            panic!("{}", error);
+                        }
+                    }
+                    pub fn #try_send_single(&mut self, value: #ty) -> Result<(), SinkError> {
                         let mut input = Input::default();
                         input.#input_fn_name(value);
-                        self.send(input);
+                        self.try_send(input)
                     }
                 };
                 ift.take_all = quote! {
@@ -374,8 +1429,32 @@ impl Generate for NameNode<'_> {
                     }
                 };
                 ift.check_input = quote! {
-                    && (self.slots.#name.is_some() || input.#income.is_none())
+                    if self.slots.#name.is_none() && input.#income.is_some() {
+                        return Err(SinkError::MissingCard(#name_str));
+                    }
                 };
+                ift.journal_write = quote! {
+                    if let Some(value) = &self.#income {
+                        entry.insert(String::from(#name_str), serde_json::to_value(value)?);
+                    }
+                };
+                ift.journal_read = quote! {
+                    if let Some(value) = entry.remove(#name_str) {
+                        input.#income = Some(serde_json::from_value(value)?);
+                    }
+                };
+                ift.transaction_fn = quote! {
+                    pub fn #input_fn_name(mut self, value: #ty) -> Self {
+                        self.input.#input_fn_name(value);
+                        self
+                    }
+                };
+                ift.script_fn.extend(quote! {
+                    pub fn #input_fn_name(mut self, value: #ty) -> Self {
+                        self.input.get_or_insert_with(Input::default).#input_fn_name(value);
+                        self
+                    }
+                });
             }
             _ => {}
         };
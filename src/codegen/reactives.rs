@@ -1,26 +1,30 @@
-use super::{change_prefix, temp_prefix, val_prefix, Generate, InterfaceTokens};
-use crate::analysis::{ChangedNode, Family, FilterNode, FoldNode, MapNode, NodeData, ReNode};
+use super::{change_prefix, temp_prefix, val_prefix, Generate, Incoming, InterfaceTokens};
+use crate::analysis::{
+    is_inferred, ChangedNode, DelayNode, Family, FilterNode, FoldNode, HoldNode, MapNode,
+    MergeNode, NodeData, ReNode, Role, SnapshotNode,
+};
+use crate::parser::{ReClosure, ReFunction};
 use proc_macro2::{Ident, TokenStream};
 use quote::format_ident;
 use quote::quote;
+use syn::{parse_quote, Expr, Pat, Type};
 
 impl Generate for MapNode<'_> {
-    fn generate_interface(&self, incoming: &Vec<&ReNode>) -> InterfaceTokens {
+    fn generate_interface(&self, incoming: &Incoming) -> InterfaceTokens {
         let mut ift = InterfaceTokens::default();
         let name = self.ident();
-        let args = &self.update_expr.inputs;
-        let return_type = &self.update_expr.return_type;
-        let body = &self.update_expr.body;
-        ift.functions = quote! {
-            #[inline]
-            fn #name (#args) -> #return_type
-                #body
-        };
-
         let family = self.family();
-        let (event_condition, var_condition) = generate_condition(incoming.clone(), family);
-        let method_args = generate_method_args(incoming.clone());
+        let (event_condition, var_condition) = generate_condition(incoming.to_vec(), family);
+        let method_args = generate_method_args(incoming.to_vec());
         let ty = self.ty();
+        let params = incoming_params(incoming);
+        let (function, call) = generate_call(&name, self.update_expr, &params, ty, method_args);
+        if let Some(function) = function {
+            ift.functions = quote! {
+                #[inline]
+                #function
+            };
+        }
         let change_name = change_prefix(&name);
         let temp_name = temp_prefix(&name);
 
@@ -32,11 +36,14 @@ impl Generate for MapNode<'_> {
                 ift.initialize_struct = quote! {
                     #name: Event::None,
                 };
+                ift.clear_event = quote! {
+                    self.#name = Event::None;
+                };
                 ift.update_part = quote! {
                     if_chain! {
                         #event_condition
                         then {
-                            state.#name = Event::Some(Self::#name(#method_args));
+                            state.#name = Event::Some(#call);
                         } else {
                             state.#name = Event::None;
                         }
@@ -48,10 +55,11 @@ impl Generate for MapNode<'_> {
                     #name: Variable<#ty>,
                 };
                 ift.update_part = quote! {
+                    state.#name.change = false;
                     if_chain! {
                         if #var_condition;
                         then {
-                            let result = Self::#name(#method_args);
+                            let result = #call;
                             if result != state.#name.value {
                                 state.#name.value = result;
                                 state.#name.change = true;
@@ -62,7 +70,7 @@ impl Generate for MapNode<'_> {
                     let #change_name = state.#name.change;
                 };
                 ift.initialize = quote! {
-                    let #temp_name = Variable { value: Self::#name(#method_args), change: false };
+                    let #temp_name = Variable { value: #call, change: false };
                     let #name = &#temp_name.value;
                 };
                 ift.initialize_observers = quote! {
@@ -78,7 +86,7 @@ impl Generate for MapNode<'_> {
                     let #temp_name = if_chain! {
                         #event_condition
                         then {
-                            Event::Some(Self::#name(#method_args))
+                            Event::Some(#call)
                         } else {
                             Event::None
                         }
@@ -87,11 +95,11 @@ impl Generate for MapNode<'_> {
                 };
             } else {
                 ift.initialize = quote! {
-                    let #temp_name = Self::#name(#method_args);
+                    let #temp_name = #call;
                     let #name = &#temp_name;
                 };
                 ift.update_part = quote! {
-                    let #temp_name = Self::#name(#method_args);
+                    let #temp_name = #call;
                     let #name = &#temp_name;
                     let #change_name = true;
                 };
@@ -105,6 +113,102 @@ impl Generate for MapNode<'_> {
     }
 }
 
+/// Parameter types for the incoming reactives in declaration order, all passed
+/// by reference.
+fn incoming_params(incoming: &[&ReNode]) -> Vec<(Type, bool)> {
+    incoming
+        .iter()
+        .rev()
+        .map(|node| (node.ty().clone(), true))
+        .collect()
+}
+
+/// Function paths are called directly with the same arguments as the function
+/// items generated for closures.
+fn generate_call(
+    name: &Ident,
+    function: &ReFunction,
+    params: &[(Type, bool)],
+    return_type: &Type,
+    args: TokenStream,
+) -> (Option<TokenStream>, TokenStream) {
+    match function {
+        ReFunction::Closure(closure) => {
+            generate_closure_call(name, closure, params, return_type, args)
+        }
+        ReFunction::Path(path) => (None, quote! { #path(#args) }),
+    }
+}
+
+/// Returns a function item and its call if the closure signature is known.
+/// Otherwise the closure is passed to a generic helper, which lets rustc infer
+/// the remaining types from the arguments.
+fn generate_closure_call(
+    name: &Ident,
+    closure: &ReClosure,
+    params: &[(Type, bool)],
+    return_type: &Type,
+    args: TokenStream,
+) -> (Option<TokenStream>, TokenStream) {
+    let inputs = closure
+        .inputs
+        .iter()
+        .zip(params)
+        .map(|(pat, (ty, by_ref))| match pat {
+            Pat::Type(_) => quote! { #pat },
+            _ if *by_ref => quote! { #pat: &#ty },
+            _ => quote! { #pat: #ty },
+        })
+        .collect::<Vec<_>>();
+    let body = &closure.body;
+    let concrete = !is_inferred(return_type)
+        && closure
+            .inputs
+            .iter()
+            .zip(params)
+            .all(|(pat, (ty, _))| matches!(pat, Pat::Type(_)) || !is_inferred(ty));
+    if concrete {
+        let body = match &**body {
+            Expr::Block(_) => quote! { #body },
+            _ => quote! { { #body } },
+        };
+        let function = quote! {
+            fn #name (#(#inputs),*) -> #return_type
+                #body
+        };
+        (Some(function), quote! { Self::#name(#args) })
+    } else {
+        let apply = format_ident!("apply_{}", name);
+        let generics = (0..params.len())
+            .map(|i| format_ident!("A{}", i))
+            .collect::<Vec<_>>();
+        let values = (0..params.len())
+            .map(|i| format_ident!("a{}", i))
+            .collect::<Vec<_>>();
+        let arg_types = params
+            .iter()
+            .zip(&generics)
+            .map(|((_, by_ref), generic)| {
+                if *by_ref {
+                    quote! { &#generic }
+                } else {
+                    quote! { #generic }
+                }
+            })
+            .collect::<Vec<_>>();
+        let call = quote! {{
+            fn #apply<F, R, #(#generics),*>(f: F, #(#values: #arg_types),*) -> R
+            where
+                F: FnOnce(#(#arg_types),*) -> R,
+            {
+                f(#(#values),*)
+            }
+            #apply(|#(#inputs),*| #body, #args)
+        }};
+        (None, call)
+    }
+}
+
 fn generate_condition(mut incoming: Vec<&ReNode>, family: Family) -> (TokenStream, TokenStream) {
     if incoming.len() == 1 {
         let node = incoming[0];
@@ -174,30 +278,39 @@ impl Generate for FoldNode<'_> {
         format_ident!("fold_{}", self.id())
     }
 
-    fn generate_interface(&self, incoming: &Vec<&ReNode>) -> InterfaceTokens {
+    fn generate_interface(&self, incoming: &Incoming) -> InterfaceTokens {
         let mut ift = InterfaceTokens::default();
         let name = self.ident();
-        let args = &self.update_expr.inputs;
-        let return_type = &self.update_expr.return_type;
-        let body = &self.update_expr.body;
-        ift.functions = quote! {
-            #[inline(always)]
-            fn #name (#args) -> #return_type
-                #body
-        };
-
         let family = self.family();
-        let (event_condition, _var_condition) = generate_condition(incoming.clone(), family);
-        let method_args = generate_method_args(incoming.clone());
+        let (event_condition, _var_condition) = generate_condition(incoming.to_vec(), family);
+        let method_args = generate_method_args(incoming.to_vec());
+        let ty = self.ty();
+        // the accumulator is passed by value in front of the inputs
+        let mut params = vec![(ty.clone(), false)];
+        params.extend(incoming_params(incoming));
+        let (function, call) = generate_call(
+            &name,
+            self.update_expr,
+            &params,
+            ty,
+            quote! { state.#name.value.clone(), #method_args },
+        );
+        if let Some(function) = function {
+            ift.functions = quote! {
+                #[inline(always)]
+                #function
+            };
+        }
         let change_name = change_prefix(&name);
         let temp_name = temp_prefix(&name);
 
         assert!(family == Family::Event);
         ift.update_part = quote! {
+            state.#name.change = false;
             if_chain! {
                 #event_condition
                 then {
-                    let result = Self::#name(state.#name.value.clone(), #method_args);
+                    let result = #call;
                     if result != state.#name.value {
                         state.#name.value = result;
                         state.#name.change = true;
@@ -207,7 +320,51 @@ impl Generate for FoldNode<'_> {
             let #name = &state.#name.value;
             let #change_name = state.#name.change;
         };
+        ift.state_struct = quote! {
+            #name: Variable<#ty>,
+        };
+
+        let init_expr = self.initial;
+        ift.initialize = quote! {
+            let #temp_name = Variable { value: #init_expr, change: false };
+            let #name = &#temp_name.value;
+        };
+        ift.initialize_observers = quote! {
+            state.#name.change = true;
+        };
+        ift.initialize_struct = quote! {
+            #name: #temp_name,
+        };
+
+        ift
+    }
+}
+
+impl Generate for HoldNode<'_> {
+    fn ident(&self) -> Ident {
+        format_ident!("hold_{}", self.id())
+    }
+
+    fn generate_interface(&self, incoming: &Incoming) -> InterfaceTokens {
+        let mut ift = InterfaceTokens::default();
+        let name = self.ident();
         let ty = self.ty();
+        let change_name = change_prefix(&name);
+        let temp_name = temp_prefix(&name);
+        assert!(incoming.len() == 1);
+        let incoming_name = incoming[0].ident();
+
+        ift.update_part = quote! {
+            state.#name.change = false;
+            if let Event::Some(val) = #incoming_name {
+                if *val != state.#name.value {
+                    state.#name.value.clone_from(val);
+                    state.#name.change = true;
+                }
+            }
+            let #name = &state.#name.value;
+            let #change_name = state.#name.change;
+        };
         ift.state_struct = quote! {
             #name: Variable<#ty>,
         };
@@ -228,23 +385,86 @@ impl Generate for FoldNode<'_> {
     }
 }
 
-impl Generate for FilterNode<'_> {
-    fn generate_interface(&self, incoming: &Vec<&ReNode>) -> InterfaceTokens {
+impl Generate for DelayNode<'_> {
+    fn ident(&self) -> Ident {
+        format_ident!("delay_{}", self.id())
+    }
+
+    fn generate_interface(&self, incoming: &Incoming) -> InterfaceTokens {
         let mut ift = InterfaceTokens::default();
         let name = self.ident();
-        let inputs = &self.filter_expr.inputs;
-        let body = &self.filter_expr.body;
+        let ty = self.ty();
+        let change_name = change_prefix(&name);
+        let temp_name = temp_prefix(&name);
+        let next_name = format_ident!("next_{}", name);
+        assert!(incoming.len() == 1);
+        let incoming_name = incoming[0].ident();
+        let incoming_change = change_prefix(&incoming_name);
 
-        ift.functions = quote! {
-            #[inline]
-            fn #name (#inputs) -> bool
-                #body
+        // value of the previous update becomes visible now
+        ift.update_part = quote! {
+            state.#name.change = false;
+            if let Some(next) = state.#next_name.take() {
+                if next != state.#name.value {
+                    state.#name.value = next;
+                    state.#name.change = true;
+                }
+            }
+            let #name = &state.#name.value;
+            let #change_name = state.#name.change;
+        };
+        // remember the current value once every reactive is up to date
+        ift.update_finish = quote! {
+            if #incoming_change {
+                state.#next_name = Some(#incoming_name.clone());
+            }
+        };
+        ift.state_struct = quote! {
+            #name: Variable<#ty>,
+            #next_name: Option<#ty>,
+        };
 
+        let init_expr = self.initial;
+        ift.initialize = quote! {
+            let #temp_name = Variable { value: #init_expr, change: false };
+            let #name = &#temp_name.value;
+        };
+        ift.initialize_finish = quote! {
+            let #next_name = Some(#incoming_name.clone());
+        };
+        ift.initialize_observers = quote! {
+            state.#name.change = true;
+        };
+        ift.initialize_struct = quote! {
+            #name: #temp_name,
+            #next_name,
         };
 
+        ift
+    }
+}
+
+impl Generate for FilterNode<'_> {
+    fn generate_interface(&self, incoming: &Incoming) -> InterfaceTokens {
+        let mut ift = InterfaceTokens::default();
+        let name = self.ident();
         let family = self.family();
-        let (event_condition, _) = generate_condition(incoming.clone(), family);
-        let method_args = generate_method_args(incoming.clone());
+        let (event_condition, _) = generate_condition(incoming.to_vec(), family);
+        let method_args = generate_method_args(incoming.to_vec());
+        let params = incoming_params(incoming);
+        let (function, call) = generate_call(
+            &name,
+            self.filter_expr,
+            &params,
+            &parse_quote! { bool },
+            method_args.clone(),
+        );
+        if let Some(function) = function {
+            ift.functions = quote! {
+                #[inline]
+                #function
+            };
+        }
 
         assert!(family == Family::Event);
         if self.pin() {
@@ -255,10 +475,13 @@ impl Generate for FilterNode<'_> {
             ift.initialize_struct = quote! {
                 #name: Event::None,
             };
+            ift.clear_event = quote! {
+                self.#name = Event::None;
+            };
             ift.update_part = quote! {
                 if_chain! {
                     #event_condition
-                    Self::#name(#method_args);
+                    if #call;
                     then {
                         state.#name = Event::Some(#method_args.clone());
                     } else {
@@ -271,7 +494,7 @@ impl Generate for FilterNode<'_> {
             ift.update_part = quote! {
                 let #name = if_chain! {
                     #event_condition
-                    Self::#name(#method_args);
+                    if #call;
                     then {
                         Event::Some(#method_args)
                     } else {
@@ -289,17 +512,18 @@ impl Generate for FilterNode<'_> {
 }
 
 impl Generate for ChangedNode {
-    fn generate_interface(&self, incoming: &Vec<&ReNode>) -> InterfaceTokens {
+    fn generate_interface(&self, incoming: &Incoming) -> InterfaceTokens {
         let mut ift = InterfaceTokens::default();
         let name = self.ident();
         let family = self.family();
         let ty = self.ty();
         assert!(incoming.len() == 1);
-        assert!(family == Family::Variable);
+        assert!(family == Family::Event);
         let incoming_name = incoming[0].ident();
+        let incoming_change = change_prefix(&incoming_name);
         if self.pin() {
             ift.update_part = quote! {
-                state.#name = if change_name {
+                state.#name = if #incoming_change {
                     Event::Some(#incoming_name.clone())
                 } else {
                     Event::None
@@ -312,9 +536,12 @@ impl Generate for ChangedNode {
             ift.initialize_struct = quote! {
                 #name: Event::None,
             };
+            ift.clear_event = quote! {
+                self.#name = Event::None;
+            };
         } else {
             ift.update_part = quote! {
-                let #name = if change_name {
+                let #name = if #incoming_change {
                     Event::Some(#incoming_name)
                 } else {
                     Event::None
@@ -328,3 +555,95 @@ impl Generate for ChangedNode {
         format_ident!("changed_{}", self.id())
     }
 }
+
+impl Generate for MergeNode {
+    fn generate_interface(&self, incoming: &Incoming) -> InterfaceTokens {
+        let mut ift = InterfaceTokens::default();
+        let name = self.ident();
+        assert!(incoming.len() == 2);
+        let left = incoming.operand(Role::Left).ident();
+        let right = incoming.operand(Role::Right).ident();
+
+        if self.pin() {
+            let ty = self.ty();
+            ift.update_part = quote! {
+                state.#name = if let Event::Some(val) = #left {
+                    Event::Some(val.clone())
+                } else if let Event::Some(val) = #right {
+                    Event::Some(val.clone())
+                } else {
+                    Event::None
+                };
+                let #name = &state.#name;
+            };
+            ift.state_struct = quote! {
+                #name: Event<#ty>,
+            };
+            ift.initialize_struct = quote! {
+                #name: Event::None,
+            };
+            ift.clear_event = quote! {
+                self.#name = Event::None;
+            };
+        } else {
+            ift.update_part = quote! {
+                let #name = if let Event::Some(val) = #left {
+                    Event::Some(val)
+                } else if let Event::Some(val) = #right {
+                    Event::Some(val)
+                } else {
+                    Event::None
+                };
+            };
+        }
+        ift
+    }
+
+    fn ident(&self) -> Ident {
+        format_ident!("merge_{}", self.id())
+    }
+}
+
+impl Generate for SnapshotNode {
+    fn generate_interface(&self, incoming: &Incoming) -> InterfaceTokens {
+        let mut ift = InterfaceTokens::default();
+        let name = self.ident();
+        assert!(incoming.len() == 2);
+        let trigger = incoming.operand(Role::Trigger).ident();
+        let sampled = incoming.operand(Role::Sampled).ident();
+
+        if self.pin() {
+            let ty = self.ty();
+            ift.update_part = quote! {
+                state.#name = if let Event::Some(_) = #trigger {
+                    Event::Some(#sampled.clone())
+                } else {
+                    Event::None
+                };
+                let #name = &state.#name;
+            };
+            ift.state_struct = quote! {
+                #name: Event<#ty>,
+            };
+            ift.initialize_struct = quote! {
+                #name: Event::None,
+            };
+            ift.clear_event = quote! {
+                self.#name = Event::None;
+            };
+        } else {
+            ift.update_part = quote! {
+                let #name = if let Event::Some(_) = #trigger {
+                    Event::Some(#sampled)
+                } else {
+                    Event::None
+                };
+            };
+        }
+        ift
+    }
+
+    fn ident(&self) -> Ident {
+        format_ident!("snapshot_{}", self.id())
+    }
+}
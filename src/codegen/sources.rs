@@ -2,12 +2,12 @@ use proc_macro2::Ident;
 use quote::format_ident;
 use quote::quote;
 
-use crate::analysis::{EvtNode, NodeData, ReNode, VarNode};
+use crate::analysis::{EvtNode, NodeData, VarNode};
 
-use super::{change_prefix, temp_prefix, Generate, InterfaceTokens};
+use super::{change_prefix, temp_prefix, Generate, Incoming, InterfaceTokens};
 
 impl Generate for VarNode<'_> {
-    fn generate_interface(&self, _: &Vec<&ReNode>) -> InterfaceTokens {
+    fn generate_interface(&self, _: &Incoming) -> InterfaceTokens {
         let name = self.ident();
         let ty = self.ty();
         let initial_state = self.initial;
@@ -16,9 +16,12 @@ impl Generate for VarNode<'_> {
         let temp_name = temp_prefix(&name);
 
         ift.update_part = quote! {
+            state.#name.change = false;
             if let Some(val) = inputs.#name {
-                state.#name.change = val != state.#name.value;
-                state.#name.value = val;
+                if val != state.#name.value {
+                    state.#name.value = val;
+                    state.#name.change = true;
+                }
             }
             let #name = &state.#name.value;
             let #change_name = state.#name.change;
@@ -32,6 +35,12 @@ impl Generate for VarNode<'_> {
             #name: Option<#ty>,
         };
 
+        ift.merge_input = quote! {
+            if other.#name.is_some() {
+                self.#name = other.#name;
+            }
+        };
+
         ift.initialize = quote! {
             let #temp_name = Variable { value: #initial_state, change: false };
             let #name = &#temp_name.value;
@@ -54,32 +63,40 @@ impl Generate for VarNode<'_> {
 }
 
 impl Generate for EvtNode {
-    fn generate_interface(&self, _: &Vec<&ReNode>) -> InterfaceTokens {
+    fn generate_interface(&self, _: &Incoming) -> InterfaceTokens {
         let name = self.ident();
         let ty = self.ty();
 
-        let mut ift = InterfaceTokens::default();
-
-        ift.update_part = quote! {
-            if let Some(val) = inputs.#name {
-                state.#name = Event::Some(val);
-            }
-            let #name = &state.#name;
-        };
-
-        ift.state_struct = quote! {
-            #name: Event<#ty>,
-        };
-
-        ift.input_struct_part = quote! {
-            #name: Option<#ty>,
-        };
-
-        ift.initialize_struct = quote! {
-            #name: Event::None,
-        };
-
-        ift
+        InterfaceTokens {
+            update_part: quote! {
+                state.#name = match inputs.#name {
+                    Some(val) => Event::Some(val),
+                    None => Event::None,
+                };
+                let #name = &state.#name;
+            },
+            state_struct: quote! {
+                #name: Event<#ty>,
+            },
+            clear_event: quote! {
+                self.#name = Event::None;
+            },
+            input_struct_part: quote! {
+                #name: Option<#ty>,
+            },
+            merge_input: quote! {
+                if other.#name.is_some() {
+                    self.#name = other.#name;
+                }
+            },
+            check_event: quote! {
+                || self.#name.is_some()
+            },
+            initialize_struct: quote! {
+                #name: Event::None,
+            },
+            ..Default::default()
+        }
     }
 
     fn ident(&self) -> Ident {
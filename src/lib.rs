@@ -1,5 +1,5 @@
-use analysis::visitor::ReVisitor;
-use codegen::generate;
+use analysis::{validate::validate, visitor::ReVisitor};
+use codegen::{generate, generate_warnings};
 use parser::ReBlock;
 use proc_macro::TokenStream;
 use syn::parse_macro_input;
@@ -12,10 +12,15 @@ mod parser;
 pub fn rerust(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as ReBlock);
     let mut visitor = ReVisitor::new();
-    let result = visitor.visit_reblock(&input);
-    if result.is_err() {
-        return result.unwrap_err().to_compile_error().into();
+    if let Err(error) = visitor.visit_reblock(&input) {
+        return error.to_compile_error().into();
     }
+    if let Err(error) = validate(&visitor.graph, &visitor.origins) {
+        return error.to_compile_error().into();
+    }
+    let warnings = generate_warnings(&visitor.warnings);
     let graph = visitor.reactive_graph();
-    generate(&graph).into()
+    let mut tokens = generate(&graph, &input);
+    tokens.extend(warnings);
+    tokens.into()
 }
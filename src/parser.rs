@@ -3,19 +3,49 @@ use syn::{
     parse::{Parse, ParseStream},
     punctuated::Punctuated,
     token::{self, Let, Semi},
-    Block, Error, Expr, Ident, Pat, PatType, Token, Type,
+    Attribute, Block, Error, Expr, ExprBlock, ExprPath, Ident, Pat, PatType, Token, Type,
+    Visibility,
 };
 use token::{Comma, Paren, RArrow};
 
 #[derive(Debug)]
 pub struct ReBlock {
+    pub attrs: Vec<Attribute>,
+    pub header: Option<ReHeader>,
     pub stmts: Vec<ReLocal>,
 }
 
 #[derive(Debug)]
+pub enum ReHeader {
+    Mod(ModHeader),
+    Program(ProgramHeader),
+}
+
+#[derive(Debug)]
+// tokens that are only needed for parsing are kept, like in syn
+#[allow(dead_code)]
+pub struct ModHeader {
+    pub vis: Visibility,
+    pub mod_token: Token![mod],
+    pub ident: Ident,
+    pub semi_token: Semi,
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct ProgramHeader {
+    pub vis: Visibility,
+    pub program_token: kw::program,
+    pub ident: Ident,
+    pub semi_token: Semi,
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
 pub struct ReLocal {
     pub let_token: Let,
     pub pin_token: Option<kw::pin>,
+    pub observe_token: Option<kw::observe>,
     pub ident: ReIdent,
     pub eq_token: Token![=],
     pub init: ReExpr,
@@ -28,6 +58,7 @@ pub struct ReIdent {
 }
 
 #[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
 pub enum ReExpr {
     Var(VarExpr),
     Evt(EvtExpr),
@@ -37,6 +68,10 @@ pub enum ReExpr {
     Map(MapExpr),
     Filter(FilterExpr),
     Changed(ChangedExpr),
+    Merge(MergeExpr),
+    Snapshot(SnapshotExpr),
+    Hold(HoldExpr),
+    Delay(DelayExpr),
 }
 
 #[derive(Debug)]
@@ -46,6 +81,7 @@ pub struct GroupExpr {
 }
 
 #[derive(Debug)]
+#[allow(dead_code)]
 pub struct VarExpr {
     pub var_token: kw::Var,
     pub colon2_token: Token![::],
@@ -57,6 +93,7 @@ pub struct VarExpr {
 }
 
 #[derive(Debug)]
+#[allow(dead_code)]
 pub struct EvtExpr {
     pub evt_token: kw::Evt,
     pub colon2_token: Token![::],
@@ -67,15 +104,18 @@ pub struct EvtExpr {
 }
 
 #[derive(Debug)]
+#[allow(dead_code)]
 pub struct MapExpr {
     pub left_expr: Box<ReExpr>,
     pub map_token: kw::map,
     pub dot_token: Token![.],
+    pub ty: Option<Type>,
     pub paren: Paren,
-    pub closure: ReClosure,
+    pub function: ReFunction,
 }
 
 #[derive(Debug)]
+#[allow(dead_code)]
 pub struct ChangedExpr {
     pub left_expr: Box<ReExpr>,
     pub changed_token: kw::changed,
@@ -84,33 +124,82 @@ pub struct ChangedExpr {
 }
 
 #[derive(Debug)]
+pub struct MergeExpr {
+    pub left_expr: Box<ReExpr>,
+    pub or_token: Token![||],
+    pub right_expr: Box<ReExpr>,
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct SnapshotExpr {
+    pub left_expr: Box<ReExpr>,
+    pub snapshot_token: kw::snapshot,
+    pub dot_token: Token![.],
+    pub paren: Paren,
+    pub var_expr: Box<ReExpr>,
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct HoldExpr {
+    pub left_expr: Box<ReExpr>,
+    pub hold_token: kw::hold,
+    pub dot_token: Token![.],
+    pub paren: Paren,
+    pub init_expr: Expr,
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub struct DelayExpr {
+    pub left_expr: Box<ReExpr>,
+    pub delay_token: kw::delay,
+    pub dot_token: Token![.],
+    pub ty: Option<Type>,
+    pub paren: Paren,
+    pub init_expr: Expr,
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
 pub struct FoldExpr {
     pub left_expr: Box<ReExpr>,
     pub fold_token: kw::fold,
     pub dot_token: Token![.],
+    pub ty: Option<Type>,
     pub paren: Paren,
     pub init_expr: Expr,
     pub comma_token: Token![,],
-    pub closure: ReClosure,
+    pub function: ReFunction,
 }
 
 #[derive(Debug)]
+#[allow(dead_code)]
 pub struct FilterExpr {
     pub left_expr: Box<ReExpr>,
     pub filter_token: kw::filter,
     pub dot_token: Token![.],
     pub paren: Paren,
-    pub closure: ReClosure,
+    pub function: ReFunction,
+}
+
+#[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
+pub enum ReFunction {
+    Closure(ReClosure),
+    Path(ExprPath),
 }
 
 #[derive(Debug)]
+#[allow(dead_code)]
 pub struct ReClosure {
     pub or1_token: Token![|],
     pub inputs: Punctuated<Pat, Comma>,
     pub or2_token: Token![|],
-    pub output_arrow: RArrow,
-    pub return_type: Type,
-    pub body: Box<Block>,
+    pub output_arrow: Option<RArrow>,
+    pub return_type: Option<Type>,
+    pub body: Box<Expr>,
 }
 
 pub mod kw {
@@ -119,29 +208,64 @@ pub mod kw {
     syn::custom_keyword!(filter);
     syn::custom_keyword!(map);
     syn::custom_keyword!(fold);
+    syn::custom_keyword!(snapshot);
+    syn::custom_keyword!(hold);
+    syn::custom_keyword!(delay);
     syn::custom_keyword!(Var);
     syn::custom_keyword!(Evt);
+    syn::custom_keyword!(program);
+    syn::custom_keyword!(observe);
 }
 
 fn is_keyword(str: String) -> bool {
-    match str.as_str() {
-        "Var" | "Evt" | "map" | "fold" | "filter" => true,
-        _ => false,
-    }
+    matches!(
+        str.as_str(),
+        "Var" | "Evt" | "map" | "fold" | "filter" | "snapshot" | "hold" | "delay"
+    )
 }
 
 /*
-    ReLet := 'let' <ident> '=' ReExpr ';'
+    ReBlock := ( '#![sync]' | '#![futures]' | '#![serde]' | '#![journal]' | '#![script]' )* ReHeader? ReLet*
+    ReHeader := RUST_VIS? 'mod' <ident> ';' | RUST_VIS? 'program' <ident> ';'
+    ReLet := 'let' ( 'pin' | 'observe' )? <ident> '=' ReExpr ';'
     ReExpr := Literal | Grouping | Binary
     Literal := <ident> | <ReExprStruct>
     Grouping := '(' ReExpr ( ',' ReExpr )* ')'
     Binary := ReExpr '.' ReTransform | ReExpr '||' ReExpr
-    ReTransform := 'map' '(' RUST_CLOSURE ')' | 'fold' '(' RUST_EXPR ',' RUST_CLOSURE ')'
+    ReTransform := 'map' Turbofish? '(' ReFunction ')'
+                 | 'fold' Turbofish? '(' RUST_EXPR ',' ReFunction ')'
+                 | 'filter' '(' ReFunction ')' | 'changed' '(' ')' | 'snapshot' '(' ReExpr ')'
+                 | 'hold' '(' RUST_EXPR ')' | 'delay' Turbofish? '(' RUST_EXPR ')'
+    Turbofish := '::' '<' RUST_TYPE '>'
+    ReFunction := RUST_CLOSURE | RUST_PATH
     ReExprStruct := 'Var' '(' RUST_EXPR ')' | 'Evt' '(' ')'
 */
 
 impl Parse for ReBlock {
     fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_inner)?;
+        for attr in &attrs {
+            let known = ATTRIBUTES.iter().any(|name| attr.path.is_ident(name));
+            if !known || !attr.tokens.is_empty() {
+                let expected: Vec<_> = ATTRIBUTES
+                    .iter()
+                    .map(|name| format!("`#![{}]`", name))
+                    .collect();
+                return Err(Error::new_spanned(
+                    attr,
+                    format!("unknown attribute, expected one of {}", expected.join(", ")),
+                ));
+            }
+        }
+        let header = if input.peek(Token![mod])
+            || input.peek(kw::program)
+            || input.peek(Token![pub])
+            || input.peek(Token![crate])
+        {
+            Some(input.parse()?)
+        } else {
+            None
+        };
         //loop over Local statements https://docs.rs/syn/1.0.53/src/syn/stmt.rs.html#110
         let mut let_stmts = Vec::new();
         loop {
@@ -151,15 +275,80 @@ impl Parse for ReBlock {
             let stmt: ReLocal = input.parse()?;
             let_stmts.push(stmt);
         }
-        Ok(Self { stmts: let_stmts })
+        Ok(Self {
+            attrs,
+            header,
+            stmts: let_stmts,
+        })
+    }
+}
+
+const ATTRIBUTES: [&str; 5] = ["sync", "futures", "serde", "journal", "script"];
+
+impl ReBlock {
+    pub fn is_sync(&self) -> bool {
+        self.has_attribute("sync")
+    }
+
+    pub fn is_futures(&self) -> bool {
+        self.has_attribute("futures")
+    }
+
+    pub fn is_serde(&self) -> bool {
+        self.has_attribute("serde")
+    }
+
+    pub fn is_journal(&self) -> bool {
+        self.has_attribute("journal")
+    }
+
+    pub fn is_script(&self) -> bool {
+        self.has_attribute("script")
+    }
+
+    fn has_attribute(&self, name: &str) -> bool {
+        self.attrs.iter().any(|attr| attr.path.is_ident(name))
+    }
+}
+
+impl Parse for ReHeader {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let vis = input.parse()?;
+        let lookahead = input.lookahead1();
+        if lookahead.peek(Token![mod]) {
+            Ok(ReHeader::Mod(ModHeader {
+                vis,
+                mod_token: input.parse()?,
+                ident: input.parse()?,
+                semi_token: input.parse()?,
+            }))
+        } else if lookahead.peek(kw::program) {
+            Ok(ReHeader::Program(ProgramHeader {
+                vis,
+                program_token: input.parse()?,
+                ident: input.parse()?,
+                semi_token: input.parse()?,
+            }))
+        } else {
+            Err(lookahead.error())
+        }
     }
 }
 
 impl Parse for ReLocal {
     fn parse(input: ParseStream) -> syn::Result<Self> {
+        let let_token = input.parse()?;
+        let pin_token: Option<kw::pin> = input.parse()?;
+        // `observe` is only a keyword in front of the name of the reactive
+        let observe_token = if pin_token.is_none() && input.peek2(Ident) {
+            input.parse()?
+        } else {
+            None
+        };
         Ok(ReLocal {
-            let_token: input.parse()?,
-            pin_token: input.parse()?,
+            let_token,
+            pin_token,
+            observe_token,
             ident: input.parse()?,
             eq_token: input.parse()?,
             init: input.parse()?,
@@ -170,8 +359,18 @@ impl Parse for ReLocal {
 
 impl Parse for ReExpr {
     fn parse(input: ParseStream) -> syn::Result<Self> {
-        let method_call = parse_method(input)?;
-        Ok(method_call)
+        let mut expr = parse_method(input)?;
+        // '||' binds weaker than method calls and is left associative
+        while input.peek(Token![||]) {
+            let or_token = input.parse()?;
+            let right_expr = parse_method(input)?;
+            expr = ReExpr::Merge(MergeExpr {
+                left_expr: Box::new(expr),
+                or_token,
+                right_expr: Box::new(right_expr),
+            });
+        }
+        Ok(expr)
     }
 }
 
@@ -250,6 +449,16 @@ impl Parse for GroupExpr {
     }
 }
 
+impl Parse for ReFunction {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(Token![|]) {
+            Ok(ReFunction::Closure(input.parse()?))
+        } else {
+            Ok(ReFunction::Path(input.parse()?))
+        }
+    }
+}
+
 impl Parse for ReClosure {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         let or1_token = input.parse()?;
@@ -276,13 +485,28 @@ impl Parse for ReClosure {
             let punct: Token![,] = input.parse()?;
             inputs.push_punct(punct);
         }
+        let or2_token = input.parse()?;
+        // a return type requires a block, like in plain Rust closures
+        let (output_arrow, return_type, body) = if input.peek(Token![->]) {
+            let output_arrow = input.parse()?;
+            let return_type = input.parse()?;
+            let block: Block = input.parse()?;
+            let body = Expr::Block(ExprBlock {
+                attrs: Vec::new(),
+                label: None,
+                block,
+            });
+            (Some(output_arrow), Some(return_type), body)
+        } else {
+            (None, None, input.parse()?)
+        };
         Ok(ReClosure {
             or1_token,
             inputs,
-            or2_token: input.parse()?,
-            output_arrow: input.parse()?,
-            return_type: input.parse()?,
-            body: Box::new(input.parse()?),
+            or2_token,
+            output_arrow,
+            return_type,
+            body: Box::new(body),
         })
     }
 }
@@ -294,19 +518,21 @@ fn parse_method(input: ParseStream) -> syn::Result<ReExpr> {
         let dot: Token![.] = input.parse()?;
         if input.peek(kw::map) {
             let map_token: kw::map = input.parse()?;
+            let ty = parse_turbofish(input)?;
             let paren = parenthesized!(content in input);
-            let closure = content.parse()?;
+            let function = content.parse()?;
             expr = ReExpr::Map(MapExpr {
                 left_expr: Box::new(expr),
                 dot_token: dot,
                 map_token,
+                ty,
                 paren,
-                closure,
+                function,
             })
         } else if input.peek(kw::filter) {
             let filter_token: kw::filter = input.parse()?;
             let paren = parenthesized!(content in input);
-            let closure = content.parse()?;
+            let function = content.parse()?;
             if let ReExpr::Group(groupexpr) = expr {
                 return Err(Error::new(
                     groupexpr.paren.span,
@@ -318,21 +544,23 @@ fn parse_method(input: ParseStream) -> syn::Result<ReExpr> {
                 dot_token: dot,
                 filter_token,
                 paren,
-                closure,
+                function,
             })
         } else if input.peek(kw::fold) {
             let fold_token: kw::fold = input.parse()?;
+            let ty = parse_turbofish(input)?;
             let paren = parenthesized!(content in input);
             let init: Expr = content.parse()?;
             let comma = content.parse()?;
-            let closure = content.parse()?;
+            let function = content.parse()?;
             expr = ReExpr::Fold(FoldExpr {
                 left_expr: Box::new(expr),
                 dot_token: dot,
                 fold_token,
+                ty,
                 paren,
                 init_expr: init,
-                closure,
+                function,
                 comma_token: comma,
             })
         } else if input.peek(kw::changed) {
@@ -353,18 +581,94 @@ fn parse_method(input: ParseStream) -> syn::Result<ReExpr> {
                 changed_token,
                 paren,
             })
+        } else if input.peek(kw::snapshot) {
+            let snapshot_token: kw::snapshot = input.parse()?;
+            let paren = parenthesized!(content in input);
+            let var_expr: ReExpr = content.parse()?;
+            if let ReExpr::Group(groupexpr) = expr {
+                return Err(Error::new(
+                    groupexpr.paren.span,
+                    "signal group not allowed as input to snapshot",
+                ));
+            }
+            if let ReExpr::Group(groupexpr) = var_expr {
+                return Err(Error::new(
+                    groupexpr.paren.span,
+                    "cannot snapshot group of reactives",
+                ));
+            }
+            expr = ReExpr::Snapshot(SnapshotExpr {
+                left_expr: Box::new(expr),
+                dot_token: dot,
+                snapshot_token,
+                paren,
+                var_expr: Box::new(var_expr),
+            })
+        } else if input.peek(kw::hold) {
+            let hold_token: kw::hold = input.parse()?;
+            let paren = parenthesized!(content in input);
+            let init: Expr = content.parse()?;
+            if let ReExpr::Group(groupexpr) = expr {
+                return Err(Error::new(
+                    groupexpr.paren.span,
+                    "signal group not allowed as input to hold",
+                ));
+            }
+            expr = ReExpr::Hold(HoldExpr {
+                left_expr: Box::new(expr),
+                dot_token: dot,
+                hold_token,
+                paren,
+                init_expr: init,
+            })
+        } else if input.peek(kw::delay) {
+            let delay_token: kw::delay = input.parse()?;
+            let ty = parse_turbofish(input)?;
+            let paren = parenthesized!(content in input);
+            let init: Expr = content.parse()?;
+            if let ReExpr::Group(groupexpr) = expr {
+                return Err(Error::new(
+                    groupexpr.paren.span,
+                    "signal group not allowed as input to delay",
+                ));
+            }
+            expr = ReExpr::Delay(DelayExpr {
+                left_expr: Box::new(expr),
+                dot_token: dot,
+                delay_token,
+                ty,
+                paren,
+                init_expr: init,
+            })
         }
     }
     Ok(expr)
 }
 
+fn parse_turbofish(input: ParseStream) -> syn::Result<Option<Type>> {
+    if !input.peek(Token![::]) {
+        return Ok(None);
+    }
+    input.parse::<Token![::]>()?;
+    input.parse::<Token![<]>()?;
+    let ty = input.parse()?;
+    input.parse::<Token![>]>()?;
+    Ok(Some(ty))
+}
+
 fn parse_primary(input: ParseStream) -> syn::Result<ReExpr> {
     if input.peek(kw::Var) {
         Ok(ReExpr::Var(input.parse()?))
     } else if input.peek(kw::Evt) {
         Ok(ReExpr::Evt(input.parse()?))
     } else if input.peek(token::Paren) {
-        Ok(ReExpr::Group(input.parse()?))
+        let mut group: GroupExpr = input.parse()?;
+        // parentheses around a single expression only group syntactically
+        if group.exprs.len() == 1 && !group.exprs.trailing_punct() {
+            Ok(group.exprs.pop().unwrap().into_value())
+        } else {
+            Ok(ReExpr::Group(group))
+        }
     } else {
         Ok(ReExpr::Ident(input.parse()?))
     }
@@ -0,0 +1,60 @@
+use std::rc::Rc;
+
+mod common;
+
+use common::collect;
+
+mod generated {
+    use rerust::rerust;
+
+    rerust! {
+        let x = Var::<u32>(0u32);
+        let e = Evt::<u32>();
+        let pin y = x.map(|x: &u32| -> u32 { *x });
+        let pin seen = e.snapshot(x);
+        let pin sum = e.fold(0u32, |sum: u32, e: &u32| -> u32 { sum + e });
+    }
+}
+
+use generated::{Coalesce, RunStatus};
+
+fn run(mode: Coalesce) -> (Vec<u32>, Vec<u32>, Vec<u32>) {
+    let mut prog = generated::Program::new();
+    let mut sink = prog.sink();
+    let (y, y_cb) = collect::<u32>();
+    let (seen, seen_cb) = collect::<u32>();
+    let (sum, sum_cb) = collect::<u32>();
+    prog.observe_y(Rc::downgrade(&y_cb));
+    prog.observe_seen(Rc::downgrade(&seen_cb));
+    prog.observe_sum(Rc::downgrade(&sum_cb));
+    prog.init();
+
+    sink.send_x(1);
+    sink.send_x(2);
+    sink.send_e(10);
+    sink.send_x(3);
+    sink.send_e(20);
+    sink.send_x(4);
+    assert_eq!(prog.run_coalesced(mode), RunStatus::Empty);
+
+    let y = y.borrow().clone();
+    let seen = seen.borrow().clone();
+    let sum = sum.borrow().clone();
+    (y, seen, sum)
+}
+
+#[test]
+fn coalesce_keeps_event_ticks() {
+    let (y, seen, sum) = run(Coalesce::KeepEvents);
+    assert_eq!(y, vec![0, 2, 3, 4]);
+    assert_eq!(seen, vec![2, 3]);
+    assert_eq!(sum, vec![0, 10, 30]);
+}
+
+#[test]
+fn coalesce_merges_events() {
+    let (y, seen, sum) = run(Coalesce::MergeEvents);
+    assert_eq!(y, vec![0, 4]);
+    assert_eq!(seen, vec![4]);
+    assert_eq!(sum, vec![0, 20]);
+}
@@ -0,0 +1,36 @@
+// shared by the integration tests, each of them uses only some of it
+#![allow(dead_code)]
+
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    sync::{Arc, Mutex},
+};
+
+pub type Observer<T> = Rc<RefCell<dyn FnMut(&T)>>;
+
+/// Values and an observer that pushes to them, for `observe_<name>`.
+pub fn collect<T: Clone + 'static>() -> (Rc<RefCell<Vec<T>>>, Observer<T>) {
+    let values = Rc::new(RefCell::new(Vec::new()));
+    let sink = values.clone();
+    let observer = Rc::new(RefCell::new(move |v: &T| sink.borrow_mut().push(v.clone())));
+    (values, observer)
+}
+
+/// Closure that pushes to `values`, for `subscribe_<name>`.
+pub fn recorder<T: Clone + 'static>(values: &Rc<RefCell<Vec<T>>>) -> impl FnMut(&T) + 'static {
+    let values = values.clone();
+    move |v: &T| values.borrow_mut().push(v.clone())
+}
+
+pub type SyncObserver<T> = Arc<Mutex<dyn FnMut(&T) + Send>>;
+
+/// Like `collect`, for programs with `#![sync]`.
+pub fn collect_sync<T: Clone + Send + 'static>() -> (Arc<Mutex<Vec<T>>>, SyncObserver<T>) {
+    let values = Arc::new(Mutex::new(Vec::new()));
+    let sink = values.clone();
+    let observer = Arc::new(Mutex::new(move |v: &T| {
+        sink.lock().unwrap().push(v.clone())
+    }));
+    (values, observer)
+}
@@ -0,0 +1,9 @@
+// Every diagnostic of the parser and the analysis has a case in tests/ui, the
+// expected output is stored next to it. Regenerate with TRYBUILD=overwrite.
+// Cases in tests/ui/pass are close to a diagnostic but accepted.
+#[test]
+fn diagnostics() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+    t.pass("tests/ui/pass/*.rs");
+}
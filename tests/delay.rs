@@ -0,0 +1,65 @@
+use std::rc::Rc;
+
+mod common;
+
+use common::collect;
+
+mod counter {
+    use rerust::rerust;
+
+    rerust! {
+        let tick = Evt::<()>();
+        let step = count.delay::<u32>(0u32).map(|c: &u32| -> u32 { if *c >= 3 { 10 } else { 1 } });
+        let pin count = tick.snapshot(step).fold(0u32, |count: u32, step: &u32| -> u32 { count + step });
+    }
+}
+
+mod previous {
+    use rerust::rerust;
+
+    rerust! {
+        let x = Var::<u32>(1u32);
+        let pin prev = x.delay(0u32);
+        let pin prev_changed = prev.changed();
+        let constant = same.delay::<u32>(7u32);
+        let pin same = constant.map(|c: &u32| -> u32 { *c });
+    }
+}
+
+#[test]
+fn counter_feeds_back_previous_value() {
+    let mut prog = counter::Program::new();
+    let mut sink = prog.sink();
+    let (count, count_cb) = collect::<u32>();
+    prog.observe_count(Rc::downgrade(&count_cb));
+    prog.init();
+
+    for _ in 0..5 {
+        sink.send_tick(());
+        prog.run();
+    }
+
+    assert_eq!(*count.borrow(), vec![0, 1, 2, 3, 13, 23]);
+}
+
+#[test]
+fn delay_lags_one_update() {
+    let mut prog = previous::Program::new();
+    let mut sink = prog.sink();
+    let (prev, prev_cb) = collect::<u32>();
+    let (prev_changed, prev_changed_cb) = collect::<u32>();
+    let (same, same_cb) = collect::<u32>();
+    prog.observe_prev(Rc::downgrade(&prev_cb));
+    prog.observe_prev_changed(Rc::downgrade(&prev_changed_cb));
+    prog.observe_same(Rc::downgrade(&same_cb));
+    prog.init();
+
+    for x in &[2u32, 2, 3, 3] {
+        sink.send_x(*x);
+        prog.run();
+    }
+
+    assert_eq!(*prev.borrow(), vec![0, 1, 2, 3]);
+    assert_eq!(*prev_changed.borrow(), vec![1, 2, 3]);
+    assert_eq!(*same.borrow(), vec![7]);
+}
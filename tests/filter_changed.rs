@@ -0,0 +1,71 @@
+use std::rc::Rc;
+
+mod common;
+
+use common::collect;
+
+mod filter {
+    use rerust::rerust;
+
+    rerust! {
+        let number = Evt::<u32>();
+        let pin large = number.filter(|n: &u32| -> bool { *n > 10 });
+        let small = number.filter(|n: &u32| -> bool { *n < 10 });
+        let pin half = small.map(|n: &u32| -> u32 { n / 2 });
+    }
+}
+
+// `parity` is implicitly pinned, which is reported as a deprecation warning
+#[allow(deprecated)]
+mod changed {
+    use rerust::rerust;
+
+    rerust! {
+        let x = Var::<u32>(0u32);
+        let parity = x.map(|x: &u32| -> u32 { x % 2 });
+        let pin x_changed = x.changed();
+        let pin parity_changed = parity.changed();
+        let pin count = parity_changed.fold(0u32, |count: u32, _p: &u32| -> u32 { count + 1 });
+    }
+}
+
+#[test]
+fn filter_drops_events() {
+    let mut prog = filter::Program::new();
+    let mut sink = prog.sink();
+    let (large, large_cb) = collect::<u32>();
+    let (half, half_cb) = collect::<u32>();
+    prog.observe_large(Rc::downgrade(&large_cb));
+    prog.observe_half(Rc::downgrade(&half_cb));
+    prog.init();
+
+    for n in &[4u32, 11, 7, 20] {
+        sink.send_number(*n);
+        prog.run();
+    }
+
+    assert_eq!(*large.borrow(), vec![11, 20]);
+    assert_eq!(*half.borrow(), vec![2, 3]);
+}
+
+#[test]
+fn changed_fires_on_difference() {
+    let mut prog = changed::Program::new();
+    let mut sink = prog.sink();
+    let (x_changed, x_cb) = collect::<u32>();
+    let (parity_changed, parity_cb) = collect::<u32>();
+    let (count, count_cb) = collect::<u32>();
+    prog.observe_x_changed(Rc::downgrade(&x_cb));
+    prog.observe_parity_changed(Rc::downgrade(&parity_cb));
+    prog.observe_count(Rc::downgrade(&count_cb));
+    prog.init();
+
+    for x in &[0u32, 2, 3, 3, 5, 6] {
+        sink.send_x(*x);
+        prog.run();
+    }
+
+    assert_eq!(*x_changed.borrow(), vec![2, 3, 5, 6]);
+    assert_eq!(*parity_changed.borrow(), vec![1, 0]);
+    assert_eq!(*count.borrow(), vec![0, 1, 2]);
+}
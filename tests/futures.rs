@@ -0,0 +1,113 @@
+use futures::{executor::block_on, SinkExt, StreamExt};
+
+mod generated {
+    use rerust::rerust;
+
+    rerust! {
+        #![futures]
+        let x = Var::<u32>(1u32);
+        let e = Evt::<u32>();
+        let pin y = x.map(|x: &u32| -> u32 { x * 2 });
+        let pin sum = e.fold(0u32, |sum: u32, e: &u32| -> u32 { sum + e });
+    }
+}
+
+mod sync {
+    use rerust::rerust;
+
+    rerust! {
+        #![sync]
+        #![futures]
+        let e = Evt::<u32>();
+        let pin count = e.fold(0u32, |count: u32, _e: &u32| -> u32 { count + 1 });
+    }
+}
+
+#[test]
+fn run_async_awaits_inputs() {
+    let mut prog = generated::Program::new();
+    let mut sink = prog.sink();
+    let mut y = prog.stream_y();
+    let mut sum = prog.stream_sum();
+    prog.init();
+
+    block_on(async {
+        SinkExt::send(&mut sink, generated::Input::default())
+            .await
+            .unwrap();
+        sink.send_x(3);
+        sink.send_e(4);
+        sink.send_e(5);
+        for _ in 0..4 {
+            prog.run_async().await;
+        }
+    });
+
+    drop(prog);
+    assert_eq!(block_on(y.by_ref().collect::<Vec<_>>()), vec![2, 6]);
+    assert_eq!(block_on(sum.by_ref().collect::<Vec<_>>()), vec![0, 4, 9]);
+}
+
+#[test]
+fn sink_forwards_stream() {
+    let mut prog = generated::Program::new();
+    let mut sink = prog.sink();
+    let sum = prog.stream_sum();
+    prog.init();
+
+    let inputs = (1..=3).map(|i| {
+        let mut input = generated::Input::default();
+        input.set_e(i);
+        Ok(input)
+    });
+    block_on(sink.send_all(&mut futures::stream::iter(inputs))).unwrap();
+    for _ in 0..3 {
+        prog.run();
+    }
+
+    drop(prog);
+    assert_eq!(block_on(sum.collect::<Vec<_>>()), vec![0, 1, 3, 6]);
+}
+
+#[test]
+fn sync_streams_are_send() {
+    let mut prog = sync::Program::new();
+    let mut sink = prog.sink();
+    let count = prog.stream_count();
+    prog.init();
+
+    let worker = std::thread::spawn(move || block_on(count.take(3).collect::<Vec<_>>()));
+    std::thread::spawn(move || {
+        sink.send_e(1);
+        sink.send_e(1);
+    })
+    .join()
+    .unwrap();
+    block_on(async {
+        prog.run_async().await;
+        prog.run_async().await;
+    });
+
+    assert_eq!(worker.join().unwrap(), vec![0, 1, 2]);
+}
+
+#[test]
+fn sink_reports_errors() {
+    let mut prog = generated::Program::new();
+    let mut sink = prog.sink();
+    let mut clone = sink.clone();
+    let mut input = generated::Input::default();
+    input.set_e(1);
+
+    let error = block_on(SinkExt::send(&mut clone, input)).unwrap_err();
+    assert_eq!(
+        error,
+        generated::SendError::Sink(generated::SinkError::MissingCard("e"))
+    );
+
+    drop(prog);
+    let mut input = generated::Input::default();
+    input.set_e(1);
+    let error = block_on(SinkExt::send(&mut sink, input)).unwrap_err();
+    assert!(matches!(error, generated::SendError::Channel(_)));
+}
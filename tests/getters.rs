@@ -0,0 +1,47 @@
+mod generated {
+    use rerust::rerust;
+
+    rerust! {
+        let x = Var::<u32>(1u32);
+        let e = Evt::<u32>();
+        let pin y = x.map(|x: &u32| -> u32 { x * 2 });
+        let pin large = e.filter(|e: &u32| -> bool { *e > 3 });
+    }
+}
+
+use generated::{Input, Program, State, StateView};
+
+#[test]
+fn getters_read_current_values() {
+    let mut prog = Program::new();
+    let mut sink = prog.sink();
+    prog.init();
+    assert_eq!(*prog.get_y(), 2);
+    assert_eq!(prog.get_large(), None);
+
+    sink.send_x(3);
+    sink.send_e(4);
+    prog.run();
+    assert_eq!(*prog.get_y(), 6);
+    assert_eq!(prog.get_large(), None);
+
+    prog.run();
+    assert_eq!(prog.get_large(), Some(&4));
+    sink.send_e(2);
+    prog.run();
+    assert_eq!(prog.get_large(), None);
+    assert_eq!(*prog.view().get_y(), 6);
+}
+
+#[test]
+fn view_reads_detached_state() {
+    let mut state = State::default();
+    let mut input = Input::default();
+    input.set_x(5);
+    input.set_e(8);
+    Program::update(&mut state, input);
+
+    let view = StateView::new(&state);
+    assert_eq!(*view.get_y(), 10);
+    assert_eq!(view.get_large(), Some(&8));
+}
@@ -0,0 +1,71 @@
+use std::rc::Rc;
+
+mod common;
+
+use common::collect;
+
+mod programs {
+    use rerust::rerust;
+
+    fn triple(x: &u32) -> u32 {
+        x * 3
+    }
+
+    rerust! {
+        pub program Doubler;
+        let x = Var::<u32>(1u32);
+        let pin y = x.map(|x: &u32| -> u32 { x * 2 });
+    }
+
+    rerust! {
+        pub program Tripler;
+        let x = Var::<u32>(1u32);
+        let pin y = x.map::<u32>(triple);
+    }
+
+    rerust! {
+        pub mod counter;
+        let e = Evt::<()>();
+        let pin count = e.fold(0u32, |count: u32, _e: &()| -> u32 { count + 1 });
+    }
+}
+
+use programs::{counter, Doubler, DoublerSink, Tripler};
+
+#[test]
+fn programs_live_side_by_side() {
+    let mut doubler = Doubler::new();
+    let mut tripler = Tripler::new();
+    let mut doubler_sink: DoublerSink = doubler.sink();
+    let mut tripler_sink = tripler.sink();
+    let (doubled, doubled_cb) = collect::<u32>();
+    let (tripled, tripled_cb) = collect::<u32>();
+    doubler.observe_y(Rc::downgrade(&doubled_cb));
+    tripler.observe_y(Rc::downgrade(&tripled_cb));
+    doubler.init();
+    tripler.init();
+
+    doubler_sink.send_x(2);
+    tripler_sink.send_x(2);
+    doubler.run();
+    tripler.run();
+
+    assert_eq!(*doubled.borrow(), vec![2, 4]);
+    assert_eq!(*tripled.borrow(), vec![3, 6]);
+}
+
+#[test]
+fn mod_header_generates_module() {
+    let mut prog = counter::Program::new();
+    let mut sink = prog.sink();
+    let (count, count_cb) = collect::<u32>();
+    prog.observe_count(Rc::downgrade(&count_cb));
+    prog.init();
+
+    for _ in 0..2 {
+        sink.send_e(());
+        prog.run();
+    }
+
+    assert_eq!(*count.borrow(), vec![0, 1, 2]);
+}
@@ -0,0 +1,124 @@
+use std::{cell::RefCell, rc::Rc};
+
+mod common;
+
+use common::recorder;
+
+mod generated {
+    use rerust::rerust;
+
+    rerust! {
+        let x = Var::<u32>(0u32);
+        let e = Evt::<u32>();
+        let pin y = x.map(|x: &u32| -> u32 { x * 2 });
+        let pin sum = e.fold(0u32, |sum: u32, e: &u32| -> u32 { sum + e });
+        let pin fired = e.map(|e: &u32| -> u32 { *e });
+    }
+}
+
+use generated::Program;
+
+#[test]
+fn undo_and_redo_restore_states() {
+    let mut prog = Program::new();
+    let mut sink = prog.sink();
+    let y = Rc::new(RefCell::new(Vec::new()));
+    let sum = Rc::new(RefCell::new(Vec::new()));
+    let fired = Rc::new(RefCell::new(Vec::new()));
+    let _y = prog.subscribe_y(recorder(&y));
+    let _sum = prog.subscribe_sum(recorder(&sum));
+    let _fired = prog.subscribe_fired(recorder(&fired));
+    prog.keep_history(10);
+    prog.init();
+
+    sink.send_x(1);
+    sink.send_e(5);
+    sink.send_x(2);
+    prog.run_all();
+    assert_eq!(prog.tick(), 3);
+
+    assert!(prog.undo());
+    assert_eq!(*prog.get_y(), 2);
+    assert_eq!(*prog.get_sum(), 5);
+    assert!(prog.undo());
+    assert_eq!(*prog.get_sum(), 0);
+    assert!(prog.undo());
+    assert_eq!(prog.tick(), 0);
+    assert!(!prog.undo());
+    assert!(prog.redo());
+    assert!(prog.goto(3));
+    assert!(!prog.redo());
+
+    assert_eq!(*y.borrow(), vec![0, 2, 4, 2, 0, 2, 4]);
+    assert_eq!(*sum.borrow(), vec![0, 5, 0, 5]);
+    assert_eq!(*fired.borrow(), vec![5]);
+}
+
+#[test]
+fn new_input_discards_redo() {
+    let mut prog = Program::new();
+    let mut sink = prog.sink();
+    prog.keep_history(10);
+    prog.init();
+
+    sink.send_x(1);
+    sink.send_x(2);
+    prog.run_all();
+    prog.undo();
+    sink.send_x(7);
+    prog.run();
+
+    assert_eq!(prog.tick(), 2);
+    assert_eq!(*prog.get_y(), 14);
+    assert!(!prog.redo());
+    assert!(prog.goto(1));
+    assert_eq!(*prog.get_y(), 2);
+}
+
+#[test]
+fn history_is_bounded() {
+    let mut prog = Program::new();
+    let mut sink = prog.sink();
+    prog.keep_history(2);
+    prog.init();
+
+    for x in 1..=3 {
+        sink.send_x(x);
+    }
+    prog.run_all();
+
+    assert!(prog.undo());
+    assert!(!prog.undo());
+    assert!(!prog.goto(0));
+    assert_eq!(*prog.get_y(), 4);
+}
+
+#[test]
+fn history_is_disabled_by_default() {
+    let mut prog = Program::new();
+    let mut sink = prog.sink();
+    prog.init();
+    sink.send_x(1);
+    prog.run();
+
+    assert!(!prog.undo());
+    assert_eq!(*prog.get_y(), 2);
+}
+
+#[test]
+fn restored_events_do_not_fire() {
+    let mut prog = Program::new();
+    let mut sink = prog.sink();
+    prog.keep_history(10);
+    prog.init();
+
+    sink.send_e(5);
+    sink.send_x(1);
+    prog.run_all();
+    assert_eq!(prog.get_fired(), None);
+
+    assert!(prog.undo());
+    assert_eq!(*prog.get_sum(), 5);
+    assert_eq!(prog.get_fired(), None);
+    assert_eq!(prog.view().get_fired(), None);
+}
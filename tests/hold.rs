@@ -0,0 +1,39 @@
+use std::rc::Rc;
+
+mod common;
+
+use common::collect;
+
+mod generated {
+    use rerust::rerust;
+
+    rerust! {
+        let input = Evt::<String>();
+        let pin latest = input.hold(String::from("none"));
+        let pin length = latest.map(|l: &String| -> usize { l.len() });
+        let pin latest_changed = latest.changed();
+    }
+}
+
+#[test]
+fn hold_keeps_latest_event() {
+    let mut prog = generated::Program::new();
+    let mut sink = prog.sink();
+    let (latest, latest_cb) = collect::<String>();
+    let (length, length_cb) = collect::<usize>();
+    let (changed, changed_cb) = collect::<String>();
+    prog.observe_latest(Rc::downgrade(&latest_cb));
+    prog.observe_length(Rc::downgrade(&length_cb));
+    prog.observe_latest_changed(Rc::downgrade(&changed_cb));
+    prog.init();
+
+    for text in &["a", "a", "abc", "xyz"] {
+        sink.send_input(String::from(*text));
+        prog.run();
+    }
+    prog.run();
+
+    assert_eq!(*latest.borrow(), vec!["none", "a", "abc", "xyz"]);
+    assert_eq!(*length.borrow(), vec![4, 1, 3]);
+    assert_eq!(*changed.borrow(), vec!["a", "abc", "xyz"]);
+}
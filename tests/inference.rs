@@ -0,0 +1,44 @@
+use std::rc::Rc;
+
+mod common;
+
+use common::collect;
+
+mod generated {
+    use rerust::rerust;
+
+    rerust! {
+        let x = Var::<u32>(1u32);
+        let words = Evt::<String>();
+        let doubled = x.map(|x| x * 2);
+        let shifted = (doubled, x).map(|d, x| d + x);
+        let pin total = shifted.map::<u64>(|s| u64::from(*s) * 10);
+        let lengths = words.map(|w| w.len()).filter(|l| *l > 2);
+        let pin longest = lengths.fold::<usize>(0, |max, l| max.max(*l));
+        let pin counted = words.fold(0u32, |count: u32, _w| -> u32 { count + 1 });
+    }
+}
+
+#[test]
+fn untyped_closures_infer_types() {
+    let mut prog = generated::Program::new();
+    let mut sink = prog.sink();
+    let (total, total_cb) = collect::<u64>();
+    let (longest, longest_cb) = collect::<usize>();
+    let (counted, counted_cb) = collect::<u32>();
+    prog.observe_total(Rc::downgrade(&total_cb));
+    prog.observe_longest(Rc::downgrade(&longest_cb));
+    prog.observe_counted(Rc::downgrade(&counted_cb));
+    prog.init();
+
+    sink.send_x(2);
+    prog.run();
+    for word in &["a", "abcd", "abc", "abcdef"] {
+        sink.send_words(String::from(*word));
+        prog.run();
+    }
+
+    assert_eq!(*total.borrow(), vec![30, 60]);
+    assert_eq!(*longest.borrow(), vec![0, 4, 6]);
+    assert_eq!(*counted.borrow(), vec![0, 1, 2, 3, 4]);
+}
@@ -0,0 +1,95 @@
+use std::{cell::RefCell, io, rc::Rc};
+
+mod generated {
+    use rerust::rerust;
+
+    rerust! {
+        #![journal]
+        let x = Var::<u32>(1u32);
+        let name = Evt::<String>();
+        let pin y = x.map(|x: &u32| -> u32 { x * 2 });
+        let pin names = name.fold(Vec::new(), |mut names: Vec<String>, name: &String| -> Vec<String> {
+            names.push(name.clone());
+            names
+        });
+    }
+}
+
+mod sync {
+    use rerust::rerust;
+
+    rerust! {
+        #![sync]
+        #![journal]
+        let x = Var::<u32>(1u32);
+        let pin y = x.map(|x: &u32| -> u32 { x * 2 });
+    }
+}
+
+use generated::Program;
+
+#[derive(Clone, Default)]
+struct Buffer(Rc<RefCell<Vec<u8>>>);
+
+impl io::Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn journal_records_applied_inputs() {
+    let buffer = Buffer::default();
+    let mut prog = Program::new();
+    let mut sink = prog.sink();
+    prog.set_journal(buffer.clone());
+    prog.init();
+
+    sink.send_x(3);
+    sink.transaction()
+        .set_x(4)
+        .set_name(String::from("Bob"))
+        .commit()
+        .unwrap();
+    prog.run_all();
+
+    let journal = String::from_utf8(buffer.0.borrow().clone()).unwrap();
+    assert_eq!(journal, "{\"x\":3}\n{\"name\":\"Bob\",\"x\":4}\n");
+    assert!(prog.journal_error().is_none());
+}
+
+#[test]
+fn replay_rebuilds_state() {
+    let journal = "{\"x\":3}\n\n{\"name\":\"Bob\"}\n{\"name\":\"Alice\",\"x\":5}\n";
+    let mut prog = Program::new();
+    prog.init();
+
+    assert_eq!(prog.replay(journal.as_bytes()).unwrap(), 3);
+    assert_eq!(*prog.get_y(), 10);
+    assert_eq!(*prog.get_names(), vec!["Bob", "Alice"]);
+}
+
+#[test]
+fn replay_rejects_unknown_sources() {
+    let mut prog = Program::new();
+    prog.init();
+
+    let error = prog.replay("{\"z\":3}\n".as_bytes()).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    assert!(error.to_string().contains("unknown source `z`"));
+}
+
+#[test]
+fn sync_journal_is_send() {
+    fn assert_send<T: Send>(_: &T) {}
+    let mut prog = sync::Program::new();
+    prog.set_journal(io::sink());
+    assert_send(&prog);
+    assert_eq!(prog.replay("{\"x\":2}\n".as_bytes()).unwrap(), 1);
+    assert_eq!(*prog.get_y(), 4);
+}
@@ -0,0 +1,47 @@
+use std::rc::Rc;
+
+mod common;
+
+use common::collect;
+
+mod generated {
+    use rerust::rerust;
+
+    rerust! {
+        let left = Evt::<u32>();
+        let right = Evt::<u32>();
+        let pin merged = left || right;
+        let pin scaled = (left.map(|l: &u32| -> u32 { l * 10 }) || right).map(|v: &u32| -> u32 { v + 1 });
+        let total = left || right.filter(|r: &u32| -> bool { *r > 1 }) || left;
+        let pin sum = total.fold(0u32, |sum: u32, v: &u32| -> u32 { sum + v });
+    }
+}
+
+#[test]
+fn merge_is_left_biased() {
+    let mut prog = generated::Program::new();
+    let mut sink = prog.sink();
+    let (merged, merged_cb) = collect::<u32>();
+    let (scaled, scaled_cb) = collect::<u32>();
+    let (sum, sum_cb) = collect::<u32>();
+    prog.observe_merged(Rc::downgrade(&merged_cb));
+    prog.observe_scaled(Rc::downgrade(&scaled_cb));
+    prog.observe_sum(Rc::downgrade(&sum_cb));
+    prog.init();
+
+    sink.send_left(1);
+    prog.run();
+    sink.send_right(2);
+    prog.run();
+    let mut input = generated::Input::default();
+    input.set_left(3);
+    input.set_right(4);
+    sink.send(input);
+    prog.run();
+    sink.send_right(1);
+    prog.run();
+
+    assert_eq!(*merged.borrow(), vec![1, 2, 3, 1]);
+    assert_eq!(*scaled.borrow(), vec![11, 3, 31, 2]);
+    assert_eq!(*sum.borrow(), vec![0, 1, 3, 6]);
+}
@@ -0,0 +1,45 @@
+use std::{cell::RefCell, rc::Rc};
+
+mod common;
+
+use common::recorder;
+
+// implicit pinning is reported as a deprecation warning
+#[allow(deprecated)]
+mod generated {
+    use rerust::rerust;
+
+    rerust! {
+        let x = Var::<u32>(1u32);
+        let e = Evt::<u32>();
+        let doubled = x.map(|x: &u32| -> u32 { x * 2 });
+        let observe y = doubled;
+        let observe z = (doubled, x).map(|d: &u32, x: &u32| -> u32 { d + x });
+        let observe scaled = (e, x).map(|e: &u32, x: &u32| -> u32 { e * x });
+        let steps = x.changed();
+        let pin count = steps.fold(0u32, |count: u32, _x: &u32| -> u32 { count + 1 });
+    }
+}
+
+#[test]
+fn observe_unpinned_reactives() {
+    let mut prog = generated::Program::new();
+    let mut sink = prog.sink();
+    let y = Rc::new(RefCell::new(Vec::new()));
+    let z = Rc::new(RefCell::new(Vec::new()));
+    let scaled = Rc::new(RefCell::new(Vec::new()));
+    let _y = prog.subscribe_y(recorder(&y));
+    let _z = prog.subscribe_z(recorder(&z));
+    let _scaled = prog.subscribe_scaled(recorder(&scaled));
+    prog.init();
+
+    sink.send_x(2);
+    sink.send_e(5);
+    sink.send_x(2);
+    prog.run_all();
+
+    assert_eq!(*y.borrow(), vec![2, 4]);
+    assert_eq!(*z.borrow(), vec![3, 6]);
+    assert_eq!(*scaled.borrow(), vec![10]);
+    assert_eq!(*prog.get_count(), 1);
+}
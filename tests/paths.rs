@@ -0,0 +1,62 @@
+use std::rc::Rc;
+
+mod common;
+
+use common::collect;
+
+// signatures mirror the reactive types
+#[allow(clippy::ptr_arg)]
+mod logic {
+    pub fn double(x: &u32) -> u32 {
+        x * 2
+    }
+
+    pub fn sum(a: &u32, b: &u32) -> u32 {
+        a + b
+    }
+
+    pub fn long(word: &String) -> bool {
+        word.len() > 2
+    }
+
+    pub mod counter {
+        pub fn step(count: u32, _word: &String) -> u32 {
+            count + 1
+        }
+    }
+}
+
+mod generated {
+    use crate::logic::counter;
+    use rerust::rerust;
+
+    rerust! {
+        let x = Var::<u32>(1u32);
+        let words = Evt::<String>();
+        let doubled = x.map(crate::logic::double);
+        let pin total = (x, doubled).map::<u32>(crate::logic::sum);
+        let long = words.filter(crate::logic::long);
+        let pin counted = long.fold::<u32>(0, counter::step);
+    }
+}
+
+#[test]
+fn function_paths_are_called() {
+    let mut prog = generated::Program::new();
+    let mut sink = prog.sink();
+    let (total, total_cb) = collect::<u32>();
+    let (counted, counted_cb) = collect::<u32>();
+    prog.observe_total(Rc::downgrade(&total_cb));
+    prog.observe_counted(Rc::downgrade(&counted_cb));
+    prog.init();
+
+    sink.send_x(2);
+    prog.run();
+    for word in &["a", "abcd", "ab", "abc"] {
+        sink.send_words(String::from(*word));
+        prog.run();
+    }
+
+    assert_eq!(*total.borrow(), vec![3, 6]);
+    assert_eq!(*counted.borrow(), vec![0, 1, 2]);
+}
@@ -0,0 +1,98 @@
+use std::{rc::Rc, thread};
+
+mod common;
+
+use common::collect;
+
+mod generated {
+    use rerust::rerust;
+
+    rerust! {
+        let e = Evt::<u32>();
+        let pin sum = e.fold(0u32, |sum: u32, e: &u32| -> u32 { sum + e });
+    }
+}
+
+mod sync {
+    use rerust::rerust;
+
+    rerust! {
+        #![sync]
+        let e = Evt::<u32>();
+        let pin count = e.fold(0u32, |count: u32, _e: &u32| -> u32 { count + 1 });
+    }
+}
+
+use generated::RunStatus;
+
+#[test]
+fn run_reports_status() {
+    let mut prog = generated::Program::new();
+    let mut sink = prog.sink();
+    let (sum, sum_cb) = collect::<u32>();
+    prog.observe_sum(Rc::downgrade(&sum_cb));
+    prog.init();
+
+    assert_eq!(prog.run(), RunStatus::Empty);
+    sink.send_e(1);
+    assert_eq!(prog.run(), RunStatus::Processed);
+    assert_eq!(prog.run(), RunStatus::Empty);
+    sink.send_e(2);
+    drop(sink);
+    assert_eq!(prog.run(), RunStatus::Processed);
+    assert_eq!(prog.run(), RunStatus::Disconnected);
+    assert_eq!(*sum.borrow(), vec![0, 1, 3]);
+}
+
+#[test]
+fn run_all_drains_queue() {
+    let mut prog = generated::Program::new();
+    let mut sink = prog.sink();
+    let (sum, sum_cb) = collect::<u32>();
+    prog.observe_sum(Rc::downgrade(&sum_cb));
+    prog.init();
+
+    for i in 1..=3 {
+        sink.send_e(i);
+    }
+    assert_eq!(prog.run_all(), RunStatus::Empty);
+    sink.send_e(4);
+    drop(sink);
+    assert_eq!(prog.run_all(), RunStatus::Disconnected);
+    assert_eq!(*sum.borrow(), vec![0, 1, 3, 6, 10]);
+}
+
+#[test]
+fn run_blocking_waits_for_sinks() {
+    let mut prog = sync::Program::new();
+    let mut sink = prog.sink();
+    prog.init();
+
+    let sender = thread::spawn(move || {
+        for i in 0..3 {
+            sink.send_e(i);
+        }
+    });
+    let mut processed = 0;
+    while prog.run_blocking() == sync::RunStatus::Processed {
+        processed += 1;
+    }
+    sender.join().unwrap();
+    assert_eq!(processed, 3);
+}
+
+#[test]
+#[should_panic(expected = "sink already taken")]
+fn sink_is_handed_out_once() {
+    let mut prog = generated::Program::new();
+    let _sink = prog.sink();
+    prog.sink();
+}
+
+#[test]
+fn run_blocking_without_sink_is_empty() {
+    let mut prog = generated::Program::new();
+    prog.init();
+    assert_eq!(prog.run_blocking(), RunStatus::Empty);
+    assert_eq!(prog.run(), RunStatus::Empty);
+}
@@ -0,0 +1,95 @@
+use std::panic;
+
+mod generated {
+    use rerust::rerust;
+
+    rerust! {
+        #![script]
+        let x = Var::<u32>(0u32);
+        let e = Evt::<u32>();
+        let pin y = x.map(|x: &u32| -> u32 { x * 2 });
+        let pin sum = e.fold(0u32, |sum: u32, e: &u32| -> u32 { sum + e });
+        let pin fired = e.map(|e: &u32| -> u32 { *e });
+    }
+}
+
+// blocks without `#![script]` can pin types without `Debug`
+mod plain {
+    use rerust::rerust;
+
+    #[derive(Clone, PartialEq)]
+    pub struct Opaque(pub u32);
+
+    rerust! {
+        let x = Var::<u32>(0u32);
+        let pin opaque = x.map(|x: &u32| -> Opaque { Opaque(*x) });
+    }
+}
+
+use generated::{Program, StateView};
+
+#[test]
+fn script_matches() {
+    let mut prog = Program::new();
+    prog.init();
+    let states = prog
+        .script()
+        .set_x(1)
+        .tick()
+        .set_e(5)
+        .tick()
+        .set_x(2)
+        .set_e(3)
+        .expect_y(vec![2, 2, 4])
+        .expect_sum(vec![0, 5, 8])
+        .expect_fired(vec![None, Some(5), Some(3)])
+        .run();
+
+    assert_eq!(states.len(), 3);
+    assert_eq!(*StateView::new(&states[1]).get_y(), 2);
+    assert_eq!(prog.tick(), 3);
+    assert_eq!(*prog.get_sum(), 8);
+}
+
+#[test]
+fn empty_ticks_keep_state() {
+    let mut prog = Program::new();
+    prog.script()
+        .set_x(3)
+        .tick()
+        .tick()
+        .tick()
+        .expect_y(vec![6, 6, 6])
+        .expect_fired(vec![None, None, None])
+        .run();
+}
+
+#[test]
+fn mismatch_shows_table() {
+    let mut prog = Program::new();
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        prog.script()
+            .set_x(1)
+            .tick()
+            .set_x(2)
+            .expect_y(vec![2, 5])
+            .expect_fired(vec![None])
+            .run();
+    }));
+    let error = result.expect_err("script should fail");
+    let message = error.downcast_ref::<String>().expect("panic message");
+    assert_eq!(
+        message,
+        "script does not match the expected values\n\
+         \x20 tick | y expected | y actual | fired expected | fired actual\n\
+         \x20 1    | 2          | 2        | None           | None\n\
+         > 2    | 5          | 4        | -              | None\n"
+    );
+}
+
+#[test]
+fn plain_blocks_have_no_script() {
+    let mut prog = plain::Program::new();
+    prog.init();
+    assert_eq!(prog.get_opaque().0, 0);
+}
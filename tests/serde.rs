@@ -0,0 +1,78 @@
+use std::{cell::RefCell, rc::Rc};
+
+mod common;
+
+use common::recorder;
+
+mod generated {
+    use rerust::rerust;
+
+    rerust! {
+        #![serde]
+        let x = Var::<u32>(1u32);
+        let e = Evt::<String>();
+        let pin y = x.map(|x: &u32| -> u32 { x * 2 });
+        let pin words = e.fold(Vec::new(), |mut words: Vec<String>, e: &String| -> Vec<String> {
+            words.push(e.clone());
+            words
+        });
+        let pin last = e.map(|e: &String| -> String { e.clone() });
+    }
+}
+
+use generated::{Program, State};
+
+#[test]
+fn snapshot_survives_serialization() {
+    let mut prog = Program::new();
+    let mut sink = prog.sink();
+    prog.init();
+    sink.send_x(3);
+    sink.send_e(String::from("a"));
+    sink.send_e(String::from("b"));
+    prog.run_all();
+
+    let json = serde_json::to_string(&prog.snapshot()).unwrap();
+    let state: State = serde_json::from_str(&json).unwrap();
+
+    let mut restored = Program::new();
+    let y = Rc::new(RefCell::new(Vec::new()));
+    let words = Rc::new(RefCell::new(Vec::new()));
+    let last = Rc::new(RefCell::new(Vec::new()));
+    let _y = restored.subscribe_y(recorder(&y));
+    let _words = restored.subscribe_words(recorder(&words));
+    let _last = restored.subscribe_last(recorder(&last));
+    restored.restore(state);
+
+    assert_eq!(*y.borrow(), vec![6]);
+    assert_eq!(
+        *words.borrow(),
+        vec![vec![String::from("a"), String::from("b")]]
+    );
+    assert!(last.borrow().is_empty());
+    assert_eq!(restored.get_last(), None);
+
+    let mut sink = restored.sink();
+    sink.send_e(String::from("c"));
+    restored.run();
+    assert_eq!(restored.get_words().len(), 3);
+    assert_eq!(*restored.get_y(), 6);
+}
+
+#[test]
+fn restore_does_not_fire_events_again() {
+    let mut prog = Program::new();
+    let mut sink = prog.sink();
+    let last = Rc::new(RefCell::new(Vec::new()));
+    let _last = prog.subscribe_last(recorder(&last));
+    prog.init();
+    sink.send_e(String::from("a"));
+    prog.run();
+    assert_eq!(*last.borrow(), vec![String::from("a")]);
+
+    prog.restore(prog.snapshot());
+
+    assert_eq!(*last.borrow(), vec![String::from("a")]);
+    assert_eq!(prog.get_last(), None);
+    assert_eq!(prog.get_words().len(), 1);
+}
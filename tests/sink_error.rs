@@ -0,0 +1,51 @@
+mod generated {
+    use rerust::rerust;
+
+    rerust! {
+        let x = Var::<u32>(0u32);
+        let pin y = x.map(|x: &u32| -> u32 { x * 2 });
+    }
+}
+
+use generated::SinkError;
+
+#[test]
+fn try_send_reports_missing_card() {
+    let mut prog = generated::Program::new();
+    let mut sink = prog.sink();
+    let card = sink.pull_x().unwrap();
+
+    assert_eq!(sink.try_send_x(1), Err(SinkError::MissingCard("x")));
+    sink.push_x(card);
+    assert_eq!(sink.try_send_x(1), Ok(()));
+}
+
+#[test]
+fn try_push_rejects_foreign_card() {
+    let mut prog = generated::Program::new();
+    let mut other = generated::Program::new();
+    let mut sink = prog.sink();
+    let mut other_sink = other.sink();
+    let card = other_sink.pull_x().unwrap();
+
+    assert_eq!(sink.try_push_x(card), Err(SinkError::ForeignCard("x")));
+    assert_eq!(sink.try_send_x(1), Ok(()));
+}
+
+#[test]
+fn try_send_reports_dropped_program() {
+    let mut prog = generated::Program::new();
+    let mut sink = prog.sink();
+    drop(prog);
+
+    assert_eq!(sink.try_send_x(1), Err(SinkError::Disconnected));
+}
+
+#[test]
+#[should_panic(expected = "sink holds no card for `x`")]
+fn send_panics_on_missing_card() {
+    let mut prog = generated::Program::new();
+    let mut sink = prog.sink();
+    let _card = sink.pull_x();
+    sink.send_x(1);
+}
@@ -0,0 +1,47 @@
+use std::rc::Rc;
+
+mod common;
+
+use common::collect;
+
+mod generated {
+    use rerust::rerust;
+
+    rerust! {
+        let text = Var::<String>(String::from("initial"));
+        let click = Evt::<()>();
+        let pin submitted = click.snapshot(text);
+        let upper = text.map(|t: &String| -> String { t.to_uppercase() });
+        let pin history = click.snapshot(upper).fold(Vec::new(), |mut history: Vec<String>, t: &String| -> Vec<String> {
+            history.push(t.clone());
+            history
+        });
+    }
+}
+
+#[test]
+fn snapshot_fires_only_with_event() {
+    let mut prog = generated::Program::new();
+    let mut sink = prog.sink();
+    let (submitted, submitted_cb) = collect::<String>();
+    let (history, history_cb) = collect::<Vec<String>>();
+    prog.observe_submitted(Rc::downgrade(&submitted_cb));
+    prog.observe_history(Rc::downgrade(&history_cb));
+    prog.init();
+
+    sink.send_click(());
+    prog.run();
+    sink.send_text(String::from("a"));
+    prog.run();
+    sink.send_text(String::from("ab"));
+    prog.run();
+    sink.send_click(());
+    prog.run();
+
+    assert_eq!(*submitted.borrow(), vec!["initial", "ab"]);
+    assert_eq!(
+        history.borrow().last().unwrap(),
+        &vec![String::from("INITIAL"), String::from("AB")]
+    );
+    assert_eq!(history.borrow().len(), 3);
+}
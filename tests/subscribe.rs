@@ -0,0 +1,82 @@
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+mod generated {
+    use rerust::rerust;
+
+    rerust! {
+        let x = Var::<u32>(0u32);
+        let pin y = x.map(|x: &u32| -> u32 { x * 2 });
+    }
+}
+
+mod sync {
+    use rerust::rerust;
+
+    rerust! {
+        #![sync]
+        let x = Var::<u32>(0u32);
+        let pin y = x.map(|x: &u32| -> u32 { x * 2 });
+    }
+}
+
+#[test]
+fn subscription_lives_until_dropped() {
+    let mut prog = generated::Program::new();
+    let mut sink = prog.sink();
+    let values = Rc::new(RefCell::new(Vec::new()));
+    let pushed = values.clone();
+    let subscription = prog.subscribe_y(move |y| pushed.borrow_mut().push(*y));
+    prog.init();
+
+    sink.send_x(1);
+    prog.run();
+    drop(subscription);
+    sink.send_x(2);
+    prog.run();
+
+    assert_eq!(*values.borrow(), vec![0, 2]);
+    assert_eq!(Rc::strong_count(&values), 1);
+}
+
+#[test]
+fn unsubscribe_removes_observer() {
+    let mut prog = generated::Program::new();
+    let mut other = generated::Program::new();
+    let mut sink = prog.sink();
+    let values = Rc::new(RefCell::new(Vec::new()));
+    let pushed = values.clone();
+    let subscription = prog.subscribe_y(move |y| pushed.borrow_mut().push(*y));
+    let kept = values.clone();
+    let _kept = prog.subscribe_y(move |y| kept.borrow_mut().push(y + 100));
+    prog.init();
+
+    let foreign = other.subscribe_y(|_| {});
+    prog.unsubscribe(foreign);
+    prog.unsubscribe(subscription);
+    assert_eq!(Rc::strong_count(&values), 2);
+    sink.send_x(1);
+    prog.run();
+
+    assert_eq!(*values.borrow(), vec![0, 100, 102]);
+}
+
+#[test]
+fn sync_subscription_drops_on_other_thread() {
+    let mut prog = sync::Program::new();
+    let mut sink = prog.sink();
+    let values = Arc::new(Mutex::new(Vec::new()));
+    let pushed = values.clone();
+    let subscription = prog.subscribe_y(move |y| pushed.lock().unwrap().push(*y));
+    prog.init();
+
+    thread::spawn(move || drop(subscription)).join().unwrap();
+    sink.send_x(1);
+    prog.run();
+
+    assert_eq!(*values.lock().unwrap(), vec![0]);
+}
@@ -0,0 +1,51 @@
+use std::{sync::Arc, thread};
+
+mod common;
+
+use common::collect_sync;
+
+mod generated {
+    use rerust::rerust;
+
+    rerust! {
+        #![sync]
+        let x = Var::<u32>(0u32);
+        let e = Evt::<u32>();
+        let pin sum = e.fold(0u32, |sum: u32, e: &u32| -> u32 { sum + e });
+        let pin y = x.map(|x: &u32| -> u32 { x * 2 });
+    }
+}
+
+fn assert_send<T: Send>() {}
+
+#[test]
+fn sync_program_is_send() {
+    assert_send::<generated::Program>();
+    assert_send::<generated::Sink>();
+}
+
+#[test]
+fn sink_sends_from_other_thread() {
+    let mut prog = generated::Program::new();
+    let (sum, sum_cb) = collect_sync::<u32>();
+    let (y, y_cb) = collect_sync::<u32>();
+    prog.observe_sum(Arc::downgrade(&sum_cb));
+    prog.observe_y(Arc::downgrade(&y_cb));
+    prog.init();
+
+    let mut sink = prog.sink();
+    thread::spawn(move || {
+        for i in 1..=3 {
+            sink.send_e(i);
+        }
+        sink.send_x(5);
+    })
+    .join()
+    .unwrap();
+    for _ in 0..4 {
+        prog.run();
+    }
+
+    assert_eq!(*sum.lock().unwrap(), vec![0, 1, 3, 6]);
+    assert_eq!(*y.lock().unwrap(), vec![0, 10]);
+}
@@ -0,0 +1,78 @@
+use std::rc::Rc;
+
+mod common;
+
+use common::collect;
+
+mod generated {
+    use rerust::rerust;
+
+    rerust! {
+        let x = Var::<u32>(0u32);
+        let name = Var::<String>(String::new());
+        let pin greeting = (name, x).map(|name: &String, x: &u32| -> String { format!("{} {}", name, x) });
+    }
+}
+
+mod keywords {
+    use rerust::rerust;
+
+    // sources named like the methods of the transaction
+    rerust! {
+        let commit = Var::<u32>(0u32);
+        let pin total = commit.map(|commit: &u32| -> u32 { *commit });
+    }
+}
+
+use generated::SinkError;
+
+#[test]
+fn transaction_updates_in_one_tick() {
+    let mut prog = generated::Program::new();
+    let mut sink = prog.sink();
+    let (greeting, greeting_cb) = collect::<String>();
+    prog.observe_greeting(Rc::downgrade(&greeting_cb));
+    prog.init();
+
+    sink.transaction()
+        .set_x(1)
+        .set_name(String::from("Bob"))
+        .commit()
+        .unwrap();
+    prog.run_all();
+
+    assert_eq!(*greeting.borrow(), vec![" 0", "Bob 1"]);
+}
+
+#[test]
+fn transaction_without_card_fails() {
+    let mut prog = generated::Program::new();
+    let mut sink = prog.sink();
+    let card = sink.pull_x().unwrap();
+
+    let result = sink
+        .transaction()
+        .set_name(String::from("Bob"))
+        .set_x(1)
+        .commit();
+    assert_eq!(result, Err(SinkError::MissingCard("x")));
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "sink holds no card for `x`"
+    );
+
+    sink.push_x(card);
+    assert_eq!(sink.transaction().set_x(1).commit(), Ok(()));
+}
+
+#[test]
+fn transaction_allows_any_source_name() {
+    let mut prog = keywords::Program::new();
+    let mut sink = prog.sink();
+    prog.init();
+
+    sink.transaction().set_commit(3).commit().unwrap();
+    prog.run_all();
+
+    assert_eq!(*prog.get_total(), 3);
+}
@@ -0,0 +1,8 @@
+use rerust::rerust;
+
+rerust! {
+    let e = Evt::<u32>();
+    let c = e.changed();
+}
+
+fn main() {}
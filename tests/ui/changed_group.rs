@@ -0,0 +1,9 @@
+use rerust::rerust;
+
+rerust! {
+    let x = Var::<u32>(0u32);
+    let y = Var::<u32>(0u32);
+    let c = (x, y).changed();
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use rerust::rerust;
+
+rerust! {
+    let x = Var::<String>(String::new());
+    let y = x.map(|x: String| -> usize { x.len() });
+}
+
+fn main() {}
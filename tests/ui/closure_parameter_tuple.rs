@@ -0,0 +1,8 @@
+use rerust::rerust;
+
+rerust! {
+    let x = Var::<(u32, bool)>((0u32, false));
+    let y = x.map(|x: &(u32, u8)| -> u32 { x.0 });
+}
+
+fn main() {}
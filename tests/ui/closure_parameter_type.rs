@@ -0,0 +1,8 @@
+use rerust::rerust;
+
+rerust! {
+    let x = Var::<u32>(0u32);
+    let y = x.map(|x: &u64| -> u64 { x * 2 });
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use rerust::rerust;
+
+rerust! {
+    let x = Var::<u32>(0u32);
+    let y = x.map(|x: &u32, y: &u32| -> u32 { x + y });
+}
+
+fn main() {}
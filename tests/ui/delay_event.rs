@@ -0,0 +1,8 @@
+use rerust::rerust;
+
+rerust! {
+    let e = Evt::<u32>();
+    let d = e.delay(0u32);
+}
+
+fn main() {}
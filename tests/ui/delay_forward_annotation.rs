@@ -0,0 +1,8 @@
+use rerust::rerust;
+
+rerust! {
+    let d = x.delay(0u32);
+    let x = Var::<u32>(0u32);
+}
+
+fn main() {}
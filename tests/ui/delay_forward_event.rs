@@ -0,0 +1,8 @@
+use rerust::rerust;
+
+rerust! {
+    let d = e.delay::<u32>(0u32);
+    let e = Evt::<u32>();
+}
+
+fn main() {}
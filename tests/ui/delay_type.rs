@@ -0,0 +1,8 @@
+use rerust::rerust;
+
+rerust! {
+    let x = Var::<u32>(0u32);
+    let d = x.delay::<u64>(0u64);
+}
+
+fn main() {}
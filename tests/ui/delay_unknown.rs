@@ -0,0 +1,8 @@
+use rerust::rerust;
+
+rerust! {
+    let x = Var::<u32>(0u32);
+    let d = y.delay::<u32>(0u32);
+}
+
+fn main() {}
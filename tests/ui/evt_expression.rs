@@ -0,0 +1,7 @@
+use rerust::rerust;
+
+rerust! {
+    let e = Evt::<u32>(1u32);
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use rerust::rerust;
+
+rerust! {
+    let e = Evt::<u32>();
+    let f = Evt::<u32>();
+    let g = (e, f).filter(|e: &u32| -> bool { *e > 1 });
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use rerust::rerust;
+
+rerust! {
+    let x = Var::<u32>(0u32);
+    let f = x.filter(|x: &u32| -> bool { *x > 1 });
+}
+
+fn main() {}
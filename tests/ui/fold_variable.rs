@@ -0,0 +1,8 @@
+use rerust::rerust;
+
+rerust! {
+    let x = Var::<u32>(0u32);
+    let s = x.fold(0u32, |s: u32, x: &u32| -> u32 { s + x });
+}
+
+fn main() {}
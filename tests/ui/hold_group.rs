@@ -0,0 +1,9 @@
+use rerust::rerust;
+
+rerust! {
+    let e = Evt::<u32>();
+    let f = Evt::<u32>();
+    let h = (e, f).hold(0u32);
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+#![deny(deprecated)]
+
+use rerust::rerust;
+
+rerust! {
+    let x = Var::<u32>(0u32);
+    let doubled = x.map(|x: &u32| -> u32 { x * 2 });
+    let observe y = doubled;
+    let tripled = x.map(|x: &u32| -> u32 { x * 3 });
+    let steps = tripled.changed();
+    let odd = x.map(|x: &u32| -> bool { x % 2 == 1 }).changed();
+}
+
+fn main() {}
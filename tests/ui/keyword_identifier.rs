@@ -0,0 +1,7 @@
+use rerust::rerust;
+
+rerust! {
+    let map = Var::<u32>(0u32);
+}
+
+fn main() {}
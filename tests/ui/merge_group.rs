@@ -0,0 +1,9 @@
+use rerust::rerust;
+
+rerust! {
+    let e = Evt::<u32>();
+    let f = Evt::<u32>();
+    let m = (e, f) || e;
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use rerust::rerust;
+
+rerust! {
+    let e = Evt::<u32>();
+    let x = Var::<u32>(0u32);
+    let m = e || x;
+}
+
+fn main() {}
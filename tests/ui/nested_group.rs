@@ -0,0 +1,9 @@
+use rerust::rerust;
+
+rerust! {
+    let x = Var::<u32>(0u32);
+    let y = Var::<u32>(0u32);
+    let z = (x, (x, y)).map(|x: &u32, y: &u32| -> u32 { x + y });
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use rerust::rerust;
+
+rerust! {
+    let x = Var::<u32>(0u32);
+    let x = Var::<u32>(1u32);
+}
+
+fn main() {}
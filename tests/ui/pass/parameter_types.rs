@@ -0,0 +1,15 @@
+use rerust::rerust;
+
+type Num = u32;
+
+rerust! {
+    let x = Var::<u32>(0u32);
+    let s = Var::<String>(String::new());
+    let alias = x.map(|x: &Num| -> u32 { x * 2 });
+    let path = s.map(|s: &std::string::String| -> usize { s.len() });
+    let lifetime = x.map(|x: &'_ u32| -> u32 { *x });
+    let pin turbofish = x.map::<Num>(|x: &u32| -> u32 { *x });
+    let pin sum = (x, alias).map(|x: &u32, alias: &Num| -> u32 { x + alias });
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use rerust::rerust;
+
+rerust! {
+    let x = Var::<u32>(0u32);
+    let pin y = x.map(|x| x * 2);
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use rerust::rerust;
+
+rerust! {
+    let e = Evt::<u32>();
+    let f = Evt::<u32>();
+    let x = Var::<u32>(0u32);
+    let s = (e, f).snapshot(x);
+}
+
+fn main() {}
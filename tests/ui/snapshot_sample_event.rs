@@ -0,0 +1,9 @@
+use rerust::rerust;
+
+rerust! {
+    let e = Evt::<u32>();
+    let f = Evt::<u32>();
+    let s = e.snapshot(f);
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use rerust::rerust;
+
+rerust! {
+    let x = Var::<u32>(0u32);
+    let y = x.map::<u64>(|x: &u32| -> u32 { x * 2 });
+}
+
+fn main() {}
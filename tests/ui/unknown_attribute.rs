@@ -0,0 +1,8 @@
+use rerust::rerust;
+
+rerust! {
+    #![debug]
+    let x = Var::<u32>(0u32);
+}
+
+fn main() {}
@@ -0,0 +1,7 @@
+use rerust::rerust;
+
+rerust! {
+    let y = x.map(|x: &u32| -> u32 { x * 2 });
+}
+
+fn main() {}
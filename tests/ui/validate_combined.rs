@@ -0,0 +1,9 @@
+use rerust::rerust;
+
+rerust! {
+    let x = Var::<u32>(0u32);
+    let d = y.delay::<u32>(0u32);
+    let pin z = x.map(|x| x * 2);
+}
+
+fn main() {}